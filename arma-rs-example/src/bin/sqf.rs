@@ -0,0 +1,18 @@
+//! Writes SQF wrapper functions for the example extension.
+//!
+//! Usage: `cargo run --bin sqf -- [output directory]`
+
+use arma_rs::SqfGenerator;
+
+fn main() {
+    let dir = std::env::args().nth(1).unwrap_or_else(|| "sqf".to_string());
+    let extension = arma_rs_example::init();
+    if let Err(e) = SqfGenerator::new("arma_rs_example", "ARS")
+        .path("\\x\\arma_rs_example\\addons\\main\\functions")
+        .write(&extension, &dir)
+    {
+        eprintln!("failed to write to {}: {}", dir, e);
+        std::process::exit(1);
+    }
+    println!("wrote {} commands to {}", extension.commands().len(), dir);
+}
@@ -0,0 +1,93 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, AttributeArgs, ItemFn, Lit, Meta, NestedMeta};
+
+pub fn expand(args: AttributeArgs, item: ItemFn) -> syn::Result<TokenStream> {
+    let (group, name) = attributes(args)?;
+    let ident = &item.sig.ident;
+    let name = name.unwrap_or_else(|| ident.to_string());
+    let group = group.unwrap_or_default();
+    if name.is_empty() {
+        return Err(syn::Error::new(
+            item.sig.ident.span(),
+            "command names can not be empty",
+        ));
+    }
+    if group.split(':').any(str::is_empty) && !group.is_empty() {
+        return Err(syn::Error::new(
+            item.sig.ident.span(),
+            "group paths can not contain empty groups",
+        ));
+    }
+
+    // Two commands with the same path define the same macro at the root of the crate, which fails
+    // to compile. Macros are not symbols of the extension, unlike a static with an exported name.
+    // Commands registered at the same path by different crates are reported by `auto_commands`.
+    let marker = format_ident!(
+        "__arma_rs_command_{}__{}",
+        encode(&group),
+        encode(&name),
+        span = ident.span()
+    );
+    Ok(quote! {
+        #item
+
+        #[doc(hidden)]
+        #[macro_export]
+        macro_rules! #marker {
+            () => {};
+        }
+
+        arma_rs::inventory::submit! {
+            arma_rs::AutoCommand::new(#group, #name, |group: arma_rs::Group| group.command(#name, #ident))
+        }
+    })
+}
+
+/// Encodes `value` as part of an identifier, keeping ASCII letters and digits
+/// and writing every other byte as `_` and two hex digits.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() {
+                char::from(byte).to_string()
+            } else {
+                format!("_{:02x}", byte)
+            }
+        })
+        .collect()
+}
+
+/// Reads `#[command(group = "...", name = "...")]`, both are optional
+fn attributes(args: AttributeArgs) -> syn::Result<(Option<String>, Option<String>)> {
+    let mut group = None;
+    let mut name = None;
+    for nested in args {
+        match nested {
+            NestedMeta::Meta(Meta::NameValue(nv)) => {
+                let value = match &nv.lit {
+                    Lit::Str(s) => s.value(),
+                    lit => return Err(syn::Error::new(lit.span(), "expected a string")),
+                };
+                if nv.path.is_ident("group") {
+                    group = Some(value);
+                } else if nv.path.is_ident("name") {
+                    name = Some(value);
+                } else {
+                    return Err(syn::Error::new(
+                        nv.path.span(),
+                        "unknown command attribute, expected `group` or `name`",
+                    ));
+                }
+            }
+            nested => {
+                return Err(syn::Error::new(
+                    nested.span(),
+                    "expected `group = \"...\"` or `name = \"...\"`",
+                ))
+            }
+        }
+    }
+    Ok((group, name))
+}
@@ -0,0 +1,138 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let (name, function) = attributes(&input)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "ArmaEvent can only be derived for structs",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (to_data, from_data) = match fields {
+        Fields::Unit => (
+            quote! { arma_rs::Value::Null },
+            quote! {
+                match data {
+                    arma_rs::Value::Null => Ok(Self),
+                    _ => Err(String::from("expected null")),
+                }
+            },
+        ),
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => (
+            quote! { arma_rs::IntoArma::to_arma(&self.0) },
+            quote! {
+                Ok(Self(arma_rs::FromArma::from_arma(data.to_string())?))
+            },
+        ),
+        _ => {
+            let count = fields.len();
+            let members = fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| match &field.ident {
+                    Some(ident) => quote! { #ident },
+                    None => {
+                        let index = syn::Index::from(i);
+                        quote! { #index }
+                    }
+                })
+                .collect::<Vec<_>>();
+            let values = (0..count)
+                .map(|i| format_ident!("value_{}", i))
+                .collect::<Vec<_>>();
+            (
+                quote! {
+                    arma_rs::Value::Array(vec![
+                        #(arma_rs::IntoArma::to_arma(&self.#members),)*
+                    ])
+                },
+                quote! {
+                    match data {
+                        arma_rs::Value::Array(values) if values.len() == #count => {
+                            let mut values = values.into_iter();
+                            #(let #values = arma_rs::FromArma::from_arma(values.next().unwrap().to_string())?;)*
+                            Ok(Self {
+                                #(#members: #values,)*
+                            })
+                        }
+                        _ => Err(format!("expected an array of {} elements", #count)),
+                    }
+                },
+            )
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics arma_rs::ArmaEvent for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+            const FUNCTION: &'static str = #function;
+
+            fn to_data(&self) -> arma_rs::Value {
+                #to_data
+            }
+
+            fn from_data(data: arma_rs::Value) -> Result<Self, String> {
+                #from_data
+            }
+        }
+    })
+}
+
+/// Reads `#[event(name = "...", function = "...")]`
+fn attributes(input: &DeriveInput) -> syn::Result<(String, String)> {
+    let mut name = None;
+    let mut function = None;
+    for attr in input.attrs.iter().filter(|a| a.path.is_ident("event")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(syn::Error::new(
+                    meta.span(),
+                    "expected #[event(name = \"...\", function = \"...\")]",
+                ))
+            }
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) => {
+                    let value = match &nv.lit {
+                        Lit::Str(s) => s.value(),
+                        lit => return Err(syn::Error::new(lit.span(), "expected a string")),
+                    };
+                    if nv.path.is_ident("name") {
+                        name = Some(value);
+                    } else if nv.path.is_ident("function") {
+                        function = Some(value);
+                    } else {
+                        return Err(syn::Error::new(
+                            nv.path.span(),
+                            "unknown event attribute, expected `name` or `function`",
+                        ));
+                    }
+                }
+                nested => {
+                    return Err(syn::Error::new(
+                        nested.span(),
+                        "expected `name = \"...\"` or `function = \"...\"`",
+                    ))
+                }
+            }
+        }
+    }
+    match (name, function) {
+        (Some(name), Some(function)) => Ok((name, function)),
+        _ => Err(syn::Error::new(
+            input.ident.span(),
+            "ArmaEvent requires #[event(name = \"...\", function = \"...\")]",
+        )),
+    }
+}
@@ -1,7 +1,10 @@
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use quote::quote;
-use syn::ItemFn;
+use syn::{DeriveInput, ItemFn};
+
+mod command;
+mod event;
 
 #[proc_macro_attribute]
 pub fn arma(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -29,6 +32,7 @@ pub fn arma(_attr: TokenStream, item: TokenStream) -> TokenStream {
         &format!("{}RVExtensionRegisterCallback", prefix),
         Span::call_site(),
     );
+    let contextfn = Ident::new(&format!("{}RVExtensionContext", prefix), Span::call_site());
 
     TokenStream::from(quote! {
 
@@ -52,6 +56,10 @@ pub fn arma(_attr: TokenStream, item: TokenStream) -> TokenStream {
         arma_rs::link_args::windows::raw! {
             unsafe "/EXPORT:_RVExtensionRegisterCallback@4=_safe32_RVExtensionRegisterCallback@4"
         }
+        #[cfg(all(target_os="windows", target_arch="x86"))]
+        arma_rs::link_args::windows::raw! {
+            unsafe "/EXPORT:_RVExtensionContext@8=_safe32_RVExtensionContext@8"
+        }
 
         #[no_mangle]
         pub unsafe extern #extern_type fn #versionfn(output: *mut arma_rs_libc::c_char, size: arma_rs_libc::size_t) -> arma_rs_libc::c_int {
@@ -91,6 +99,37 @@ pub fn arma(_attr: TokenStream, item: TokenStream) -> TokenStream {
             }
         }
 
+        #[no_mangle]
+        pub unsafe extern #extern_type fn #contextfn(args: *mut *mut arma_rs_libc::c_char, arg_count: arma_rs_libc::c_int) {
+            #ext_init
+            if let Some(ext) = &RV_EXTENSION {
+                ext.handle_call_context(args, arg_count);
+            }
+        }
+
         #ast
     })
 }
+
+#[proc_macro_derive(ArmaEvent, attributes(event))]
+pub fn arma_event(item: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(item as DeriveInput);
+    event::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Registers a function as a command, mounted by `ExtensionBuilder::auto_commands`.
+///
+/// `#[command(group = "timer", name = "sleep")]`, the group defaults to the root of the extension
+/// and the name to the name of the function. Nested groups are separated by `:`.
+/// Two commands at the same path in a crate fail to compile, commands at the same path
+/// registered by different crates are reported as duplicates when the extension is built.
+#[proc_macro_attribute]
+pub fn command(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(attr as syn::AttributeArgs);
+    let item = syn::parse_macro_input!(item as ItemFn);
+    command::expand(args, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,226 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::{Message, Priority};
+use crate::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How callbacks with the same name and function are combined before being sent to Arma.
+pub struct Batch {
+    window: Duration,
+    max_len: Option<usize>,
+    coalesce: bool,
+}
+
+impl Batch {
+    #[must_use]
+    /// Collects callbacks for `window` and sends their data as a single array.
+    pub const fn window(window: Duration) -> Self {
+        Self {
+            window,
+            max_len: None,
+            coalesce: false,
+        }
+    }
+
+    #[must_use]
+    /// Keeps only the data of the latest callback sent within `window`.
+    pub const fn coalesce(window: Duration) -> Self {
+        Self {
+            window,
+            max_len: None,
+            coalesce: true,
+        }
+    }
+
+    #[must_use]
+    /// Sends the batch before the window has passed once it holds `len` callbacks.
+    pub const fn max_len(mut self, len: usize) -> Self {
+        self.max_len = Some(len);
+        self
+    }
+}
+
+struct Pending {
+    started: Instant,
+    values: Vec<Value>,
+    priority: Priority,
+}
+
+#[derive(Default)]
+/// Callbacks waiting for their batch to be sent.
+pub(crate) struct Batches {
+    pending: Mutex<HashMap<(String, String), Pending>>,
+}
+
+impl Batches {
+    /// Adds a message to its batch.
+    /// Returns what should be queued now: the message itself if it is not batched,
+    /// or its batch once full.
+    pub(crate) fn add(
+        &self,
+        config: &HashMap<(String, String), Batch>,
+        message: Message,
+    ) -> Option<Message> {
+        if config.is_empty() {
+            return Some(message);
+        }
+        let key = (message.name.clone(), message.func.clone());
+        let batch = if let Some(batch) = config.get(&key) {
+            batch
+        } else {
+            return Some(message);
+        };
+        let mut pending = self.pending.lock().unwrap();
+        let entry = pending.entry(key.clone()).or_insert_with(|| Pending {
+            started: Instant::now(),
+            values: Vec::new(),
+            priority: Priority::Low,
+        });
+        if batch.coalesce {
+            entry.values.clear();
+        }
+        entry.values.push(message.data.unwrap_or(Value::Null));
+        entry.priority = entry.priority.max(message.priority.unwrap_or_default());
+        if batch.max_len.is_some_and(|max| entry.values.len() >= max) {
+            let entry = pending.remove(&key).unwrap();
+            return Some(to_message(key, batch, entry));
+        }
+        None
+    }
+
+    /// Removes every batch whose window has passed.
+    /// Also returns when the next pending batch will be due.
+    pub(crate) fn take_due(
+        &self,
+        config: &HashMap<(String, String), Batch>,
+    ) -> (Vec<Message>, Option<Instant>) {
+        if config.is_empty() {
+            return (Vec::new(), None);
+        }
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+        let due = pending
+            .iter()
+            .filter(|(key, entry)| entry.started + config[*key].window <= now)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        let messages = due
+            .into_iter()
+            .map(|key| {
+                let entry = pending.remove(&key).unwrap();
+                let batch = &config[&key];
+                to_message(key, batch, entry)
+            })
+            .collect();
+        let next = pending
+            .iter()
+            .map(|(key, entry)| entry.started + config[key].window)
+            .min();
+        (messages, next)
+    }
+}
+
+fn to_message((name, func): (String, String), batch: &Batch, mut entry: Pending) -> Message {
+    let data = if batch.coalesce {
+        entry.values.pop().unwrap_or(Value::Null)
+    } else {
+        Value::Array(entry.values)
+    };
+    Message {
+        name,
+        func,
+        data: Some(data),
+        priority: Some(entry.priority),
+        call_id: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(batch: Batch) -> HashMap<(String, String), Batch> {
+        HashMap::from([(("name".to_string(), "func".to_string()), batch)])
+    }
+
+    fn message(func: &str, n: f64) -> Message {
+        Message::new("name", func, Some(Value::Number(n)))
+    }
+
+    #[test]
+    fn unbatched() {
+        let config = config(Batch::window(Duration::from_secs(1)));
+        let batches = Batches::default();
+        assert!(batches.add(&config, message("other", 1.0)).is_some());
+    }
+
+    #[test]
+    fn max_len() {
+        let config = config(Batch::window(Duration::from_secs(60)).max_len(3));
+        let batches = Batches::default();
+        assert!(batches.add(&config, message("func", 1.0)).is_none());
+        assert!(batches.add(&config, message("func", 2.0)).is_none());
+        let batch = batches.add(&config, message("func", 3.0)).unwrap();
+        assert_eq!(
+            batch.data,
+            Some(Value::Array(vec![
+                Value::Number(1.0),
+                Value::Number(2.0),
+                Value::Number(3.0)
+            ]))
+        );
+        assert!(batches.take_due(&config).0.is_empty());
+    }
+
+    #[test]
+    fn window() {
+        let config = config(Batch::window(Duration::from_millis(20)));
+        let batches = Batches::default();
+        assert!(batches.add(&config, message("func", 1.0)).is_none());
+        assert!(batches.add(&config, message("func", 2.0)).is_none());
+        let (due, next) = batches.take_due(&config);
+        assert!(due.is_empty());
+        assert!(next.is_some());
+        std::thread::sleep(Duration::from_millis(30));
+        let (due, next) = batches.take_due(&config);
+        assert_eq!(due.len(), 1);
+        assert_eq!(
+            due[0].data,
+            Some(Value::Array(vec![Value::Number(1.0), Value::Number(2.0)]))
+        );
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn coalesce() {
+        let config = config(Batch::coalesce(Duration::ZERO));
+        let batches = Batches::default();
+        assert!(batches.add(&config, message("func", 1.0)).is_none());
+        assert!(batches.add(&config, message("func", 2.0)).is_none());
+        let (due, _) = batches.take_due(&config);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].data, Some(Value::Number(2.0)));
+    }
+
+    #[test]
+    fn priority() {
+        let config = config(Batch::window(Duration::from_secs(60)).max_len(2));
+        let batches = Batches::default();
+        assert!(batches.add(&config, message("func", 1.0)).is_none());
+        let batch = batches
+            .add(&config, message("func", 2.0).with_priority(Priority::Low))
+            .unwrap();
+        assert_eq!(batch.priority, Some(Priority::Normal));
+        assert!(batches
+            .add(&config, message("func", 3.0).with_priority(Priority::Low))
+            .is_none());
+        let batch = batches
+            .add(&config, message("func", 4.0).with_priority(Priority::Low))
+            .unwrap();
+        assert_eq!(batch.priority, Some(Priority::Low));
+    }
+}
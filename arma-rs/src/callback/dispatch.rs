@@ -0,0 +1,268 @@
+use std::{
+    ffi::{CStr, CString},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use super::{
+    split::{split, PART_SUFFIX},
+    CallbackQueue, DeadLetter, DeadLetterReason, Message, RetryPolicy,
+};
+use crate::Value;
+
+/// A function called with callbacks that could not be delivered.
+pub(crate) type DeadLetterHook = dyn Fn(DeadLetter) + Send + Sync;
+
+/// Moves callbacks from the queue into Arma.
+pub(crate) struct Dispatcher {
+    queue: Arc<CallbackQueue>,
+    retry: RetryPolicy,
+    dead_letter: Option<Arc<DeadLetterHook>>,
+    data_limit: usize,
+    next_id: AtomicU64,
+}
+
+impl Dispatcher {
+    pub(crate) fn new(
+        queue: Arc<CallbackQueue>,
+        retry: RetryPolicy,
+        dead_letter: Option<Arc<DeadLetterHook>>,
+        data_limit: usize,
+    ) -> Self {
+        Self {
+            queue,
+            retry,
+            dead_letter,
+            data_limit,
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Delivers callbacks with `send` until the process exits.
+    pub(crate) fn run<F>(&self, mut send: F) -> !
+    where
+        F: FnMut(&CStr, &CStr, &CStr) -> libc::c_int,
+    {
+        loop {
+            if let Some(message) = self.queue.pop_timeout(Duration::from_millis(100)) {
+                self.deliver(message, &mut send);
+            }
+        }
+    }
+
+    /// Delivers a single callback, retrying according to the retry policy.
+    /// Data larger than the data limit is split and delivered in parts.
+    pub(crate) fn deliver<F>(&self, message: Message, send: &mut F)
+    where
+        F: FnMut(&CStr, &CStr, &CStr) -> libc::c_int,
+    {
+        let (name, func, parts) = if let Some(cstrings) = self.to_cstrings(&message) {
+            cstrings
+        } else {
+            error!("callback {}:{} was not valid", message.name, message.func);
+            self.fail(message, DeadLetterReason::Invalid, 0);
+            return;
+        };
+
+        let start = Instant::now();
+        for data in &parts {
+            if let Err((reason, attempts)) = self.send(&name, &func, data, start, send) {
+                self.fail(message, reason, attempts);
+                return;
+            }
+        }
+        self.queue.mark_delivered();
+    }
+
+    fn send<F>(
+        &self,
+        name: &CStr,
+        func: &CStr,
+        data: &CStr,
+        start: Instant,
+        send: &mut F,
+    ) -> Result<(), (DeadLetterReason, u32)>
+    where
+        F: FnMut(&CStr, &CStr, &CStr) -> libc::c_int,
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            if send(name, func, data) >= 0 {
+                return Ok(());
+            }
+            if self.retry.attempts_exhausted(attempts) {
+                return Err((DeadLetterReason::Rejected, attempts));
+            }
+            let delay = self.retry.delay(attempts);
+            if self.retry.deadline_exceeded(start.elapsed() + delay) {
+                return Err((DeadLetterReason::DeadlineExceeded, attempts));
+            }
+            std::thread::sleep(delay);
+        }
+    }
+
+    fn to_cstrings(&self, message: &Message) -> Option<(CString, CString, Vec<CString>)> {
+        let data = match &message.data {
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => String::new(),
+        };
+        let (func, parts) = if data.len() > self.data_limit {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            (
+                format!("{}{}", message.func, PART_SUFFIX),
+                split(&data, self.data_limit, id),
+            )
+        } else {
+            (message.func.clone(), vec![data])
+        };
+        Some((
+            CString::new(message.name.as_str()).ok()?,
+            CString::new(func).ok()?,
+            parts
+                .into_iter()
+                .map(CString::new)
+                .collect::<Result<_, _>>()
+                .ok()?,
+        ))
+    }
+
+    fn fail(&self, message: Message, reason: DeadLetterReason, attempts: u32) {
+        self.queue.mark_failed();
+        warn!(
+            "callback {}:{} was not delivered after {} attempts",
+            message.name, message.func, attempts
+        );
+        if let Some(hook) = &self.dead_letter {
+            hook(DeadLetter {
+                name: message.name,
+                func: message.func,
+                data: message.data,
+                reason,
+                attempts,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    fn dispatcher(retry: RetryPolicy) -> (Dispatcher, Arc<Mutex<Vec<DeadLetter>>>) {
+        let dead = Arc::new(Mutex::new(Vec::new()));
+        let hook = {
+            let dead = dead.clone();
+            Arc::new(move |letter| dead.lock().unwrap().push(letter))
+        };
+        let queue = Arc::new(CallbackQueue::default());
+        (Dispatcher::new(queue, retry, Some(hook), 200), dead)
+    }
+
+    #[test]
+    fn delivered() {
+        let (dispatcher, dead) = dispatcher(RetryPolicy::default());
+        let mut sent = Vec::new();
+        dispatcher.deliver(
+            Message::new("name", "func", Some(Value::String("data".into()))),
+            &mut |name: &CStr, func: &CStr, data: &CStr| {
+                sent.push((name.to_owned(), func.to_owned(), data.to_owned()));
+                0
+            },
+        );
+        assert_eq!(
+            sent,
+            vec![(
+                CString::new("name").unwrap(),
+                CString::new("func").unwrap(),
+                CString::new("data").unwrap()
+            )]
+        );
+        assert_eq!(dispatcher.queue.stats().delivered, 1);
+        assert!(dead.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn retried() {
+        let (dispatcher, _) = dispatcher(RetryPolicy::default());
+        let mut calls = 0;
+        dispatcher.deliver(Message::new("name", "func", None), &mut |_, _, _| {
+            calls += 1;
+            if calls < 3 {
+                -1
+            } else {
+                0
+            }
+        });
+        assert_eq!(calls, 3);
+        assert_eq!(dispatcher.queue.stats().delivered, 1);
+    }
+
+    #[test]
+    fn max_attempts() {
+        let (dispatcher, dead) = dispatcher(RetryPolicy::default().max_attempts(3));
+        let mut calls = 0;
+        dispatcher.deliver(Message::new("name", "func", None), &mut |_, _, _| {
+            calls += 1;
+            -1
+        });
+        assert_eq!(calls, 3);
+        let dead = dead.lock().unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].reason, DeadLetterReason::Rejected);
+        assert_eq!(dead[0].attempts, 3);
+        assert_eq!(dispatcher.queue.stats().failed, 1);
+    }
+
+    #[test]
+    fn deadline() {
+        let (dispatcher, dead) = dispatcher(
+            RetryPolicy::default()
+                .backoff(Duration::from_millis(10), Duration::from_millis(10))
+                .deadline(Duration::from_millis(25)),
+        );
+        let mut calls = 0;
+        dispatcher.deliver(Message::new("name", "func", None), &mut |_, _, _| {
+            calls += 1;
+            -1
+        });
+        assert!(calls <= 3);
+        assert_eq!(
+            dead.lock().unwrap()[0].reason,
+            DeadLetterReason::DeadlineExceeded
+        );
+    }
+
+    #[test]
+    fn split_data() {
+        let (dispatcher, _) = dispatcher(RetryPolicy::default());
+        let mut sent = Vec::new();
+        dispatcher.deliver(
+            Message::new("name", "func", Some(Value::String("X".repeat(1000)))),
+            &mut |_: &CStr, func: &CStr, data: &CStr| {
+                sent.push((func.to_owned(), data.to_owned()));
+                0
+            },
+        );
+        assert_eq!(sent.len(), 8);
+        assert!(sent
+            .iter()
+            .all(|(func, data)| func.to_str() == Ok("func:part") && data.to_bytes().len() <= 200));
+        assert_eq!(dispatcher.queue.stats().delivered, 1);
+    }
+
+    #[test]
+    fn invalid() {
+        let (dispatcher, dead) = dispatcher(RetryPolicy::default());
+        dispatcher.deliver(Message::new("na\0me", "func", None), &mut |_, _, _| {
+            panic!("invalid callbacks should not be sent")
+        });
+        assert_eq!(dead.lock().unwrap()[0].reason, DeadLetterReason::Invalid);
+    }
+}
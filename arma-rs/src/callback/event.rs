@@ -0,0 +1,31 @@
+use crate::Value;
+
+/// A callback with a fixed name and function, carrying typed data.
+///
+/// Usually implemented with `#[derive(ArmaEvent)]`:
+/// ```
+/// use arma_rs::ArmaEvent;
+///
+/// #[derive(ArmaEvent)]
+/// #[event(name = "timer", function = "done")]
+/// pub struct TimerDone {
+///     id: String,
+///     seconds: u32,
+/// }
+/// ```
+/// A struct with a single unnamed field sends that field as the data,
+/// any other struct sends its fields as an array.
+pub trait ArmaEvent: Sized {
+    /// The name of the callback.
+    const NAME: &'static str;
+    /// The function of the callback.
+    const FUNCTION: &'static str;
+
+    /// Converts the event into the data of the callback.
+    fn to_data(&self) -> Value;
+
+    /// Converts the data of a callback back into the event.
+    /// # Errors
+    /// Will return an error if the data does not match the event.
+    fn from_data(data: Value) -> Result<Self, String>;
+}
@@ -0,0 +1,223 @@
+//! Callbacks sent from the extension into Arma.
+
+use std::time::Duration;
+
+mod batch;
+mod dispatch;
+mod event;
+mod priority;
+mod queue;
+mod sender;
+mod split;
+
+pub use batch::Batch;
+pub(crate) use dispatch::{DeadLetterHook, Dispatcher};
+pub use event::ArmaEvent;
+pub use priority::Priority;
+pub(crate) use queue::{CallbackQueue, QueueConfig};
+pub use sender::CallbackSender;
+
+use crate::Value;
+
+/// The largest callback data Arma accepts, in bytes
+pub(crate) const DATA_LIMIT: usize = 10239;
+
+/// A callback waiting to be sent to Arma.
+pub(crate) struct Message {
+    pub(crate) name: String,
+    pub(crate) func: String,
+    pub(crate) data: Option<Value>,
+    pub(crate) priority: Option<Priority>,
+    pub(crate) call_id: Option<u64>,
+}
+
+impl Message {
+    pub(crate) fn new(name: &str, func: &str, data: Option<Value>) -> Self {
+        Self {
+            name: name.to_string(),
+            func: func.to_string(),
+            data,
+            priority: None,
+            call_id: None,
+        }
+    }
+
+    pub(crate) const fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub(crate) const fn with_call_id(mut self, call_id: Option<u64>) -> Self {
+        self.call_id = call_id;
+        self
+    }
+
+    /// Adds the id of the call that sent the message to its function or data.
+    pub(crate) fn correlate(&mut self, correlation: Correlation) {
+        let id = match self.call_id {
+            Some(id) => id,
+            None => return,
+        };
+        match correlation {
+            Correlation::Disabled => {}
+            Correlation::Function => self.func = format!("{}:{}", self.func, id),
+            Correlation::Payload => {
+                self.data = Some(Value::Array(vec![
+                    Value::String(id.to_string()),
+                    self.data.take().unwrap_or(Value::Null),
+                ]));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Where the id of the originating call is added to callbacks sent from a command.
+///
+/// Only callbacks sent through a [`Context`](crate::Context), or a sender obtained from one,
+/// carry a call id. The id is sent as a string, as SQF numbers can not hold every id.
+pub enum Correlation {
+    /// Callbacks are sent unchanged.
+    #[default]
+    Disabled,
+    /// The id is appended to the function, `"done"` becomes `"done:42"`.
+    /// Batched callbacks combine several calls and are sent without an id.
+    Function,
+    /// The data is wrapped with the id, `data` becomes `["42", data]`.
+    Payload,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// What to do when a callback is sent while the queue is full.
+pub enum OverflowPolicy {
+    /// Wait until the dispatcher has made space in the queue.
+    ///
+    /// Only use this for callbacks sent from other threads. The dispatcher waits for Arma,
+    /// which only reads callbacks from its main thread, so a command blocking the main thread
+    /// on a full queue can wait forever.
+    Block,
+    /// Remove the oldest callback in the queue to make space for the new one.
+    DropOldest,
+    /// Discard the new callback.
+    #[default]
+    DropNewest,
+    /// Discard the new callback and return [`CallbackError::QueueFull`].
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An error returned when a callback could not be queued.
+pub enum CallbackError {
+    /// The callback queue is full and the overflow policy is [`OverflowPolicy::Error`].
+    QueueFull,
+}
+
+impl std::fmt::Display for CallbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::QueueFull => write!(f, "callback queue is full"),
+        }
+    }
+}
+
+impl std::error::Error for CallbackError {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Counters for the callbacks sent by an extension.
+pub struct CallbackStats {
+    /// Callbacks that were handed to Arma.
+    pub delivered: u64,
+    /// Callbacks that were discarded because the queue was full.
+    pub dropped: u64,
+    /// Callbacks that could not be delivered and were given to the dead letter handler.
+    pub failed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// How the dispatcher retries a callback that Arma did not accept.
+///
+/// Arma rejects callbacks while its callback buffer is full.
+/// The default policy retries every millisecond until the callback is accepted.
+pub struct RetryPolicy {
+    max_attempts: Option<u32>,
+    backoff: Duration,
+    max_backoff: Duration,
+    deadline: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: None,
+            backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            deadline: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    #[must_use]
+    /// Gives up on a callback after it has been rejected `attempts` times.
+    pub const fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    #[must_use]
+    /// Waits `initial` after the first rejection, doubling the wait after each
+    /// following rejection up to `max`.
+    pub const fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    #[must_use]
+    /// Gives up on a callback if it could not be delivered within `deadline`
+    /// of the first attempt.
+    pub const fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    fn attempts_exhausted(&self, attempts: u32) -> bool {
+        self.max_attempts.is_some_and(|max| attempts >= max)
+    }
+
+    fn deadline_exceeded(&self, elapsed: Duration) -> bool {
+        self.deadline.is_some_and(|deadline| elapsed > deadline)
+    }
+
+    fn delay(&self, attempts: u32) -> Duration {
+        self.backoff
+            .checked_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+            .map_or(self.max_backoff, |delay| delay.min(self.max_backoff))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Why a callback could not be delivered.
+pub enum DeadLetterReason {
+    /// The name, function or data contained a nul byte.
+    Invalid,
+    /// Arma rejected the callback for the maximum number of attempts.
+    Rejected,
+    /// Arma did not accept the callback before the deadline.
+    DeadlineExceeded,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A callback that could not be delivered to Arma.
+pub struct DeadLetter {
+    /// The name of the callback.
+    pub name: String,
+    /// The function of the callback.
+    pub func: String,
+    /// The data of the callback.
+    pub data: Option<Value>,
+    /// Why the callback could not be delivered.
+    pub reason: DeadLetterReason,
+    /// The number of times the callback was sent to Arma.
+    pub attempts: u32,
+}
@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+
+use super::Message;
+
+/// The number of times a lane can be passed over for a higher priority lane
+/// before it is given a turn.
+const STARVATION_LIMIT: u32 = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The priority of a callback.
+/// Higher priority callbacks are sent to Arma first.
+pub enum Priority {
+    /// Sent after every other callback, used for telemetry and other bulk data.
+    Low,
+    #[default]
+    /// The priority of callbacks unless configured otherwise.
+    Normal,
+    /// Sent before every other callback, used for critical events.
+    High,
+}
+
+impl Priority {
+    const fn lane(self) -> usize {
+        match self {
+            Self::High => 0,
+            Self::Normal => 1,
+            Self::Low => 2,
+        }
+    }
+}
+
+#[derive(Default)]
+/// Queued callbacks, separated by priority.
+///
+/// Higher priority lanes are drained first, but a lane that has been passed over
+/// [`STARVATION_LIMIT`] times is given a turn so a flood of high priority callbacks
+/// can not hold back the others forever.
+pub(crate) struct Lanes {
+    lanes: [VecDeque<Message>; 3],
+    skipped: [u32; 3],
+}
+
+impl Lanes {
+    pub(crate) fn len(&self) -> usize {
+        self.lanes.iter().map(VecDeque::len).sum()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.lanes.iter().all(VecDeque::is_empty)
+    }
+
+    pub(crate) fn push(&mut self, message: Message) {
+        self.lanes[message.priority.unwrap_or_default().lane()].push_back(message);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Message> {
+        let starved = (0..self.lanes.len())
+            .filter(|&lane| !self.lanes[lane].is_empty() && self.skipped[lane] >= STARVATION_LIMIT)
+            .max_by_key(|&lane| self.skipped[lane]);
+        let lane =
+            starved.or_else(|| (0..self.lanes.len()).find(|&l| !self.lanes[l].is_empty()))?;
+        for other in 0..self.lanes.len() {
+            if other != lane && !self.lanes[other].is_empty() {
+                self.skipped[other] += 1;
+            }
+        }
+        self.skipped[lane] = 0;
+        self.lanes[lane].pop_front()
+    }
+
+    /// Removes the oldest callback of the lowest priority.
+    pub(crate) fn pop_lowest(&mut self) -> Option<Message> {
+        self.lanes
+            .iter_mut()
+            .rev()
+            .find(|lane| !lane.is_empty())
+            .and_then(VecDeque::pop_front)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(func: &str, priority: Priority) -> Message {
+        Message::new("test", func, None).with_priority(priority)
+    }
+
+    #[test]
+    fn high_first() {
+        let mut lanes = Lanes::default();
+        lanes.push(message("low", Priority::Low));
+        lanes.push(message("normal", Priority::Normal));
+        lanes.push(message("high", Priority::High));
+        assert_eq!(lanes.len(), 3);
+        assert_eq!(lanes.pop().unwrap().func, "high");
+        assert_eq!(lanes.pop().unwrap().func, "normal");
+        assert_eq!(lanes.pop().unwrap().func, "low");
+        assert!(lanes.pop().is_none());
+    }
+
+    #[test]
+    fn no_starvation() {
+        let mut lanes = Lanes::default();
+        lanes.push(message("low", Priority::Low));
+        for _ in 0..20 {
+            lanes.push(message("high", Priority::High));
+        }
+        let position = std::iter::from_fn(|| lanes.pop())
+            .position(|m| m.func == "low")
+            .unwrap();
+        assert_eq!(position, STARVATION_LIMIT as usize);
+    }
+
+    #[test]
+    fn pop_lowest() {
+        let mut lanes = Lanes::default();
+        lanes.push(message("high", Priority::High));
+        lanes.push(message("low", Priority::Low));
+        assert_eq!(lanes.pop_lowest().unwrap().func, "low");
+        assert_eq!(lanes.pop_lowest().unwrap().func, "high");
+    }
+}
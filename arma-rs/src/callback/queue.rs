@@ -0,0 +1,325 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use super::{
+    batch::Batches, priority::Lanes, Batch, CallbackError, CallbackStats, Correlation, Message,
+    OverflowPolicy, Priority,
+};
+
+#[derive(Default)]
+/// Settings of the queue, applied when the extension is built.
+pub(crate) struct QueueConfig {
+    pub(crate) capacity: Option<usize>,
+    pub(crate) policy: OverflowPolicy,
+    pub(crate) batches: HashMap<(String, String), Batch>,
+    pub(crate) priorities: HashMap<String, Priority>,
+    pub(crate) correlation: Correlation,
+}
+
+#[derive(Default)]
+/// The queue shared between every `Context` and the callback dispatcher.
+pub(crate) struct CallbackQueue {
+    config: RwLock<QueueConfig>,
+    messages: Mutex<Lanes>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    batches: Batches,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl CallbackQueue {
+    /// Replaces the settings of the queue.
+    /// Callbacks already queued are kept.
+    pub(crate) fn configure(&self, config: QueueConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    pub(crate) fn push(&self, mut message: Message) -> Result<(), CallbackError> {
+        let (message, policy) = {
+            let config = self.config.read().unwrap();
+            if message.priority.is_none() {
+                message.priority = config.priorities.get(&message.name).copied();
+            }
+            // Batches are matched by the function before correlation changes it,
+            // batched callbacks only keep their ids in the payload.
+            if config.correlation == Correlation::Payload {
+                message.correlate(config.correlation);
+            }
+            let message = self
+                .batches
+                .add(&config.batches, message)
+                .map(|mut message| {
+                    if config.correlation == Correlation::Function {
+                        message.correlate(config.correlation);
+                    }
+                    message
+                });
+            (message, config.policy)
+        };
+        match message {
+            Some(message) => self.enqueue(message, policy),
+            None => Ok(()),
+        }
+    }
+
+    fn enqueue(&self, message: Message, policy: OverflowPolicy) -> Result<(), CallbackError> {
+        let capacity = self.config.read().unwrap().capacity;
+        let mut messages = self.messages.lock().unwrap();
+        if let Some(capacity) = capacity {
+            if messages.len() >= capacity {
+                match policy {
+                    OverflowPolicy::Block => {
+                        while messages.len() >= capacity {
+                            messages = self.not_full.wait(messages).unwrap();
+                        }
+                    }
+                    OverflowPolicy::DropOldest => {
+                        messages.pop_lowest();
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    OverflowPolicy::DropNewest => {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                    OverflowPolicy::Error => {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                        return Err(CallbackError::QueueFull);
+                    }
+                }
+            }
+        }
+        messages.push(message);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Queues batches whose window has passed, returning when the next batch is due.
+    fn flush_batches(&self) -> Option<Instant> {
+        let (due, policy) = {
+            let config = self.config.read().unwrap();
+            (self.batches.take_due(&config.batches), config.policy)
+        };
+        let (due, next) = due;
+        for message in due {
+            // Batches are flushed by the consumer, which can not wait for itself to make space
+            let policy = match policy {
+                OverflowPolicy::Block | OverflowPolicy::Error => OverflowPolicy::DropNewest,
+                policy => policy,
+            };
+            let _ = self.enqueue(message, policy);
+        }
+        next
+    }
+
+    /// Removes the next message, waiting up to `timeout` for one to arrive.
+    pub(crate) fn pop_timeout(&self, timeout: Duration) -> Option<Message> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let next_batch = self.flush_batches();
+            let mut messages = self.messages.lock().unwrap();
+            if let Some(message) = messages.pop() {
+                self.not_full.notify_one();
+                return Some(message);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            let wake = next_batch.map_or(deadline, |next| next.min(deadline));
+            drop(
+                self.not_empty
+                    .wait_timeout_while(messages, wake.saturating_duration_since(now), |m| {
+                        m.is_empty()
+                    })
+                    .unwrap(),
+            );
+        }
+    }
+
+    pub(crate) fn mark_delivered(&self) {
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn mark_failed(&self) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> CallbackStats {
+        CallbackStats {
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Value;
+
+    fn message(func: &str) -> Message {
+        Message::new("test", func, None)
+    }
+
+    fn bounded(capacity: usize, policy: OverflowPolicy) -> CallbackQueue {
+        let queue = CallbackQueue::default();
+        queue.configure(QueueConfig {
+            capacity: Some(capacity),
+            policy,
+            ..QueueConfig::default()
+        });
+        queue
+    }
+
+    fn pop_func(queue: &CallbackQueue) -> Option<String> {
+        queue.pop_timeout(Duration::ZERO).map(|m| m.func)
+    }
+
+    #[test]
+    fn unbounded() {
+        let queue = CallbackQueue::default();
+        for i in 0..100 {
+            queue.push(message(&i.to_string())).unwrap();
+        }
+        assert_eq!(pop_func(&queue), Some("0".to_string()));
+        assert_eq!(queue.stats().dropped, 0);
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let queue = bounded(2, OverflowPolicy::DropOldest);
+        queue.push(message("a")).unwrap();
+        queue.push(message("b")).unwrap();
+        queue.push(message("c")).unwrap();
+        assert_eq!(pop_func(&queue), Some("b".to_string()));
+        assert_eq!(pop_func(&queue), Some("c".to_string()));
+        assert_eq!(pop_func(&queue), None);
+        assert_eq!(queue.stats().dropped, 1);
+    }
+
+    #[test]
+    fn drop_newest() {
+        let queue = bounded(2, OverflowPolicy::DropNewest);
+        queue.push(message("a")).unwrap();
+        queue.push(message("b")).unwrap();
+        queue.push(message("c")).unwrap();
+        assert_eq!(pop_func(&queue), Some("a".to_string()));
+        assert_eq!(pop_func(&queue), Some("b".to_string()));
+        assert_eq!(pop_func(&queue), None);
+        assert_eq!(queue.stats().dropped, 1);
+    }
+
+    #[test]
+    fn default_policy() {
+        let queue = CallbackQueue::default();
+        queue.configure(QueueConfig {
+            capacity: Some(1),
+            ..QueueConfig::default()
+        });
+        queue.push(message("a")).unwrap();
+        queue.push(message("b")).unwrap();
+        assert_eq!(pop_func(&queue), Some("a".to_string()));
+        assert_eq!(queue.stats().dropped, 1);
+    }
+
+    #[test]
+    fn error() {
+        let queue = bounded(1, OverflowPolicy::Error);
+        queue.push(message("a")).unwrap();
+        assert_eq!(queue.push(message("b")), Err(CallbackError::QueueFull));
+        assert_eq!(queue.stats().dropped, 1);
+    }
+
+    #[test]
+    fn priorities() {
+        let queue = CallbackQueue::default();
+        queue.configure(QueueConfig {
+            priorities: HashMap::from([("urgent".to_string(), Priority::High)]),
+            ..QueueConfig::default()
+        });
+        queue.push(message("normal")).unwrap();
+        queue
+            .push(Message::new("urgent", "configured", None))
+            .unwrap();
+        queue
+            .push(message("explicit").with_priority(Priority::High))
+            .unwrap();
+        assert_eq!(pop_func(&queue), Some("configured".to_string()));
+        assert_eq!(pop_func(&queue), Some("explicit".to_string()));
+        assert_eq!(pop_func(&queue), Some("normal".to_string()));
+    }
+
+    #[test]
+    fn correlation() {
+        let queue = CallbackQueue::default();
+        queue.configure(QueueConfig {
+            correlation: Correlation::Function,
+            ..QueueConfig::default()
+        });
+        queue.push(message("done").with_call_id(Some(7))).unwrap();
+        queue.push(message("other")).unwrap();
+        assert_eq!(pop_func(&queue), Some("done:7".to_string()));
+        assert_eq!(pop_func(&queue), Some("other".to_string()));
+
+        queue.configure(QueueConfig {
+            correlation: Correlation::Payload,
+            ..QueueConfig::default()
+        });
+        queue
+            .push(Message::new("test", "done", Some(Value::Boolean(true))).with_call_id(Some(7)))
+            .unwrap();
+        let message = queue.pop_timeout(Duration::ZERO).unwrap();
+        assert_eq!(message.func, "done");
+        assert_eq!(
+            message.data,
+            Some(Value::Array(vec![
+                Value::String("7".to_string()),
+                Value::Boolean(true)
+            ]))
+        );
+    }
+
+    #[test]
+    fn correlate_batches() {
+        let queue = CallbackQueue::default();
+        queue.configure(QueueConfig {
+            batches: HashMap::from([(
+                ("test".to_string(), "pos".to_string()),
+                Batch::window(Duration::from_secs(60)).max_len(2),
+            )]),
+            correlation: Correlation::Function,
+            ..QueueConfig::default()
+        });
+        queue.push(message("pos").with_call_id(Some(1))).unwrap();
+        queue.push(message("other").with_call_id(Some(2))).unwrap();
+        queue.push(message("pos").with_call_id(Some(3))).unwrap();
+        assert_eq!(pop_func(&queue), Some("other:2".to_string()));
+        assert_eq!(pop_func(&queue), Some("pos".to_string()));
+        assert_eq!(pop_func(&queue), None);
+    }
+
+    #[test]
+    fn block() {
+        let queue = std::sync::Arc::new(bounded(1, OverflowPolicy::Block));
+        queue.push(message("a")).unwrap();
+        let producer = {
+            let queue = queue.clone();
+            std::thread::spawn(move || queue.push(message("b")))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!producer.is_finished());
+        assert_eq!(pop_func(&queue), Some("a".to_string()));
+        producer.join().unwrap().unwrap();
+        assert_eq!(pop_func(&queue), Some("b".to_string()));
+        assert_eq!(queue.stats().dropped, 0);
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use super::{ArmaEvent, CallbackError, CallbackQueue, Message, Priority};
+use crate::IntoArma;
+
+#[derive(Clone)]
+/// Sends callbacks into Arma from anywhere in the extension.
+///
+/// Unlike a [`Context`](crate::Context), a sender is not tied to a command call,
+/// it can be stored and used from other threads, loggers or library callbacks.
+/// Obtained from [`ExtensionBuilder::callback_sender`](crate::ExtensionBuilder::callback_sender)
+/// or [`Context::callback_sender`](crate::Context::callback_sender).
+pub struct CallbackSender {
+    queue: Arc<CallbackQueue>,
+    call_id: Option<u64>,
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::Span>,
+}
+
+impl CallbackSender {
+    pub(crate) fn new(queue: Arc<CallbackQueue>) -> Self {
+        Self {
+            queue,
+            call_id: None,
+            #[cfg(feature = "tracing")]
+            span: None,
+        }
+    }
+
+    pub(crate) const fn with_call_id(mut self, call_id: u64) -> Self {
+        self.call_id = Some(call_id);
+        self
+    }
+
+    #[cfg(feature = "tracing")]
+    /// Links callbacks sent with this sender to the span of a command.
+    pub(crate) fn with_span(mut self, span: tracing::Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    #[must_use]
+    /// Returns the id of the call this sender was obtained from, if any.
+    pub const fn call_id(&self) -> Option<u64> {
+        self.call_id
+    }
+
+    fn push(&self, message: Message) -> Result<(), CallbackError> {
+        #[cfg(feature = "tracing")]
+        match &self.span {
+            Some(span) => {
+                tracing::debug!(parent: span, name = %message.name, func = %message.func, "callback");
+            }
+            None => tracing::debug!(name = %message.name, func = %message.func, "callback"),
+        }
+        self.queue.push(message.with_call_id(self.call_id))
+    }
+
+    /// Sends a callback into Arma
+    /// <https://community.bistudio.com/wiki/Arma_3:_Mission_Event_Handlers#ExtensionCallback>
+    ///
+    /// Data larger than Arma's callback limit is sent in multiple parts,
+    /// see the README for how to reassemble them in SQF.
+    ///
+    /// # Errors
+    /// Will return an error if the callback queue is full and the extension was built
+    /// with [`OverflowPolicy::Error`](crate::OverflowPolicy::Error).
+    pub fn callback<V>(&self, name: &str, func: &str, data: Option<V>) -> Result<(), CallbackError>
+    where
+        V: IntoArma,
+    {
+        self.push(Message::new(name, func, Some(data.into())))
+    }
+
+    /// Sends a callback into Arma ahead of callbacks with a lower priority.
+    ///
+    /// # Errors
+    /// Will return an error if the callback queue is full and the extension was built
+    /// with [`OverflowPolicy::Error`](crate::OverflowPolicy::Error).
+    pub fn callback_with_priority<V>(
+        &self,
+        name: &str,
+        func: &str,
+        data: Option<V>,
+        priority: Priority,
+    ) -> Result<(), CallbackError>
+    where
+        V: IntoArma,
+    {
+        self.push(Message::new(name, func, Some(data.into())).with_priority(priority))
+    }
+
+    /// Sends an event into Arma as a callback
+    ///
+    /// # Errors
+    /// Will return an error if the callback queue is full and the extension was built
+    /// with [`OverflowPolicy::Error`](crate::OverflowPolicy::Error).
+    pub fn emit<E>(&self, event: E) -> Result<(), CallbackError>
+    where
+        E: ArmaEvent,
+    {
+        self.push(Message::new(E::NAME, E::FUNCTION, Some(event.to_data())))
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{IntoArma, Value};
+
+/// Appended to the function of a callback that was split into parts.
+pub(crate) const PART_SUFFIX: &str = ":part";
+
+/// Bytes reserved in each part for `["<id>",<index>,<count>,""]`.
+const PART_OVERHEAD: usize = 72;
+
+/// Splits `data` into parts that each serialize to at most `limit` bytes.
+///
+/// Each part is an array of `[id, index, count, chunk]`, where joining the chunks
+/// in order gives back `data`.
+pub(crate) fn split(data: &str, limit: usize, id: u64) -> Vec<String> {
+    let budget = limit.saturating_sub(PART_OVERHEAD).max(4);
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut len = 0;
+    for c in data.chars() {
+        // Quotes are escaped by doubling them when the chunk is serialized
+        let c_len = if c == '"' { 2 } else { c.len_utf8() };
+        if len + c_len > budget && !chunk.is_empty() {
+            chunks.push(std::mem::take(&mut chunk));
+            len = 0;
+        }
+        chunk.push(c);
+        len += c_len;
+    }
+    chunks.push(chunk);
+
+    let count = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            Value::Array(vec![
+                id.to_string().to_arma(),
+                Value::Number(index as f64),
+                Value::Number(count as f64),
+                Value::String(chunk),
+            ])
+            .to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(parts: &[String]) -> String {
+        parts
+            .iter()
+            .map(|part| {
+                let chunk = part.splitn(4, ',').nth(3).unwrap();
+                chunk[1..chunk.len() - 2].replace("\"\"", "\"")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn single_part() {
+        let parts = split("hello", 100, 1);
+        assert_eq!(parts, vec![r#"["1",0,1,"hello"]"#.to_string()]);
+    }
+
+    #[test]
+    fn multiple_parts() {
+        let data = "X".repeat(1000);
+        let parts = split(&data, 200, 7);
+        assert_eq!(parts.len(), 8);
+        assert!(parts.iter().all(|part| part.len() <= 200));
+        assert!(parts[7].starts_with(r#"["7",7,8,""#));
+        assert_eq!(chunks(&parts), data);
+    }
+
+    #[test]
+    fn escaped_quotes() {
+        let data = "\"".repeat(500);
+        let parts = split(&data, 200, 0);
+        assert!(parts.iter().all(|part| part.len() <= 200));
+        assert_eq!(chunks(&parts), data);
+    }
+
+    #[test]
+    fn multibyte_chars() {
+        let data = "é".repeat(500);
+        let parts = split(&data, 100, 0);
+        assert!(parts.iter().all(|part| part.len() <= 100));
+        assert_eq!(chunks(&parts), data);
+    }
+}
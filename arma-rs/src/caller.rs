@@ -0,0 +1,156 @@
+//! Information Arma sends about who is calling the extension.
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Information about the caller of a command.
+///
+/// Arma sends it through `RVExtensionContext` before each call, since Arma 3 2.11.
+/// On older versions of Arma every field is `None` and [`Caller::is_known`] returns false.
+/// [`Caller::default`] is such an unknown caller.
+pub struct Caller {
+    known: bool,
+    steam_id: Option<String>,
+    file_source: Option<String>,
+    mission_name: Option<String>,
+    server_name: Option<String>,
+    remote_owner: Option<i32>,
+}
+
+impl Caller {
+    #[must_use]
+    /// Creates a caller with no information, as Arma sends for calls made by the server,
+    /// used to simulate calls in tests.
+    pub fn new() -> Self {
+        Self {
+            known: true,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    /// Sets the Steam ID of the caller.
+    pub fn with_steam_id<S>(mut self, steam_id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.steam_id = Some(steam_id.into());
+        self
+    }
+
+    #[must_use]
+    /// Sets the file the call was made from.
+    pub fn with_file_source<S>(mut self, file_source: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.file_source = Some(file_source.into());
+        self
+    }
+
+    #[must_use]
+    /// Sets the name of the mission.
+    pub fn with_mission_name<S>(mut self, mission_name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.mission_name = Some(mission_name.into());
+        self
+    }
+
+    #[must_use]
+    /// Sets the name of the server.
+    pub fn with_server_name<S>(mut self, server_name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    #[must_use]
+    /// Sets the machine that remote executed the call.
+    pub const fn with_remote_owner(mut self, owner: i32) -> Self {
+        self.remote_owner = Some(owner);
+        self
+    }
+
+    #[must_use]
+    /// Returns if Arma sent information about the caller, false before Arma 3 2.11.
+    pub const fn is_known(&self) -> bool {
+        self.known
+    }
+
+    #[must_use]
+    /// Returns the Steam ID of the player calling the extension.
+    /// `None` when the call was not made by a player, such as on a dedicated server.
+    pub fn steam_id(&self) -> Option<&str> {
+        self.steam_id.as_deref()
+    }
+
+    #[must_use]
+    /// Returns the file the call was made from, `None` when called from the debug console
+    /// or a script without a file.
+    pub fn file_source(&self) -> Option<&str> {
+        self.file_source.as_deref()
+    }
+
+    #[must_use]
+    /// Returns the name of the mission.
+    pub fn mission_name(&self) -> Option<&str> {
+        self.mission_name.as_deref()
+    }
+
+    #[must_use]
+    /// Returns the name of the server.
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
+    #[must_use]
+    /// Returns the machine that remote executed the call, `None` when it was called locally.
+    pub const fn remote_owner(&self) -> Option<i32> {
+        self.remote_owner
+    }
+
+    /// Reads the arguments of `RVExtensionContext`:
+    /// Steam ID, file source, mission name, server name and remote executed owner.
+    pub(crate) fn from_args(args: &[String]) -> Self {
+        let field = |index: usize| {
+            args.get(index)
+                .map(|arg| arg.trim_matches('"'))
+                .filter(|arg| !arg.is_empty() && *arg != "0")
+                .map(ToString::to_string)
+        };
+        Self {
+            known: true,
+            steam_id: field(0),
+            file_source: field(1),
+            mission_name: field(2),
+            server_name: field(3),
+            remote_owner: field(4).and_then(|owner| owner.parse().ok()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_args() {
+        let args = ["76561198000000000", "x\\mod\\fn_init.sqf", "test", "", "2"].map(String::from);
+        assert_eq!(
+            Caller::from_args(&args),
+            Caller::new()
+                .with_steam_id("76561198000000000")
+                .with_file_source("x\\mod\\fn_init.sqf")
+                .with_mission_name("test")
+                .with_remote_owner(2)
+        );
+        assert_eq!(
+            Caller::from_args(&["0", "", "", "", "0"].map(String::from)),
+            Caller::new()
+        );
+        assert_eq!(Caller::from_args(&[]), Caller::new());
+        assert!(!Caller::default().is_known());
+    }
+}
@@ -1,29 +1,37 @@
 use crate::ext_result::IntoExtResult;
 use crate::value::{FromArma, Value};
-use crate::Context;
+use crate::{Context, FromPath, Path};
 
 type HandlerFunc = Box<
     dyn Fn(
-        Context,
-        *mut libc::c_char,
-        libc::size_t,
-        Option<*mut *mut i8>,
-        Option<libc::c_int>,
-    ) -> libc::c_int,
+            Context,
+            *mut libc::c_char,
+            libc::size_t,
+            Option<*mut *mut i8>,
+            Option<libc::c_int>,
+        ) -> libc::c_int
+        + Send
+        + Sync,
 >;
 
 /// A wrapper for `HandlerFunc`
 pub struct Handler {
     /// The function to call
     pub handler: HandlerFunc,
+    pub(crate) arg_types: Vec<&'static str>,
+    pub(crate) path_types: Vec<&'static str>,
+    pub(crate) return_type: Option<&'static str>,
 }
 
 /// Create a new handler from a Factory
 pub fn fn_handler<C, I, R>(command: C) -> Handler
 where
-    C: Factory<I, R> + 'static,
+    C: Factory<I, R> + Send + Sync + 'static,
 {
     Handler {
+        arg_types: C::arg_types(),
+        path_types: C::path_types(),
+        return_type: C::return_type(),
         handler: Box::new(
             move |context: Context,
                   output: *mut libc::c_char,
@@ -52,7 +60,8 @@ pub trait Executor: 'static {
 }
 
 /// A factory for creating a command handler.
-/// Creates a handler from any function that optionally takes a context and up to 12 arguments.
+/// Creates a handler from any function that optionally takes a context, then optionally a [`Path`],
+/// and up to 12 arguments.
 /// The arguments must implement `FromArma`
 /// The return value must implement `IntoExtResult`
 pub trait Factory<A, R> {
@@ -66,6 +75,30 @@ pub trait Factory<A, R> {
         args: Option<*mut *mut i8>,
         count: Option<libc::c_int>,
     ) -> libc::c_int;
+
+    /// Returns the type names of the arguments, not including the context.
+    fn arg_types() -> Vec<&'static str>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+
+    /// Returns the type names of the path parameters, empty if no [`Path`] is taken.
+    fn path_types() -> Vec<&'static str>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+
+    /// Returns the type name of the return value, `None` if nothing is returned.
+    fn return_type() -> Option<&'static str>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
@@ -92,6 +125,14 @@ macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
         Func: Fn($($param),*),
         $($param: FromArma,)*
     {
+        fn arg_types() -> Vec<&'static str> {
+            vec![$(std::any::type_name::<$param>(),)*]
+        }
+
+        fn return_type() -> Option<&'static str> {
+            None
+        }
+
         #[allow(non_snake_case)]
         unsafe fn call(&self, _: Context, _output: *mut libc::c_char, _size: libc::size_t, args: Option<*mut *mut i8>, count: Option<libc::c_int>) -> libc::c_int{
             let count = count.unwrap_or_else(|| 0);
@@ -121,7 +162,7 @@ macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
                 };
                 #[allow(unused_variables, unused_mut)] // Caused by the 0 loop
                 let mut c = 0;
-                #[allow(unused_assignments, clippy::eval_order_dependence)]
+                #[allow(unused_assignments, clippy::mixed_read_write_in_expression)]
                 (self)($(
                     if let Ok(val) = $param::from_arma(argv.pop().unwrap()) {
                         c += 1;
@@ -141,6 +182,14 @@ macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
         Func: Fn(Context, $($param),*),
         $($param: FromArma,)*
     {
+        fn arg_types() -> Vec<&'static str> {
+            vec![$(std::any::type_name::<$param>(),)*]
+        }
+
+        fn return_type() -> Option<&'static str> {
+            None
+        }
+
         #[allow(non_snake_case)]
         unsafe fn call(&self, context: Context, _output: *mut libc::c_char, _size: libc::size_t, args: Option<*mut *mut i8>, count: Option<libc::c_int>) -> libc::c_int{
             let count = count.unwrap_or_else(|| 0);
@@ -170,7 +219,7 @@ macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
                 };
                 #[allow(unused_variables, unused_mut)] // Caused by the 0 loop
                 let mut c = 0;
-                #[allow(unused_assignments, clippy::eval_order_dependence)]
+                #[allow(unused_assignments, clippy::mixed_read_write_in_expression)]
                 (self)(context,
                 $(
                     if let Ok(val) = $param::from_arma(argv.pop().unwrap()) {
@@ -192,6 +241,14 @@ macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
         Func: Fn($($param),*) -> R,
         $($param: FromArma,)*
     {
+        fn arg_types() -> Vec<&'static str> {
+            vec![$(std::any::type_name::<$param>(),)*]
+        }
+
+        fn return_type() -> Option<&'static str> {
+            Some(std::any::type_name::<R>())
+        }
+
         #[allow(non_snake_case)]
         unsafe fn call(&self, _: Context, output: *mut libc::c_char, size: libc::size_t, args: Option<*mut *mut i8>, count: Option<libc::c_int>) -> libc::c_int {
             let count = count.unwrap_or_else(|| 0);
@@ -224,7 +281,7 @@ macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
                 };
                 #[allow(unused_variables, unused_mut)] // Caused by the 0 loop
                 let mut c = 0;
-                #[allow(unused_assignments, clippy::eval_order_dependence)]
+                #[allow(unused_assignments, clippy::mixed_read_write_in_expression)]
                 handle_output_and_return(
                     {
                         (self)($(
@@ -250,6 +307,14 @@ macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
         Func: Fn(Context, $($param),*) -> R,
         $($param: FromArma,)*
     {
+        fn arg_types() -> Vec<&'static str> {
+            vec![$(std::any::type_name::<$param>(),)*]
+        }
+
+        fn return_type() -> Option<&'static str> {
+            Some(std::any::type_name::<R>())
+        }
+
         #[allow(non_snake_case)]
         unsafe fn call(&self, context: Context, output: *mut libc::c_char, size: libc::size_t, args: Option<*mut *mut i8>, count: Option<libc::c_int>) -> libc::c_int {
             let count = count.unwrap_or_else(|| 0);
@@ -282,7 +347,7 @@ macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
                 };
                 #[allow(unused_variables, unused_mut)] // Caused by the 0 loop
                 let mut c = 0;
-                #[allow(unused_assignments, clippy::eval_order_dependence)]
+                #[allow(unused_assignments, clippy::mixed_read_write_in_expression)]
                 handle_output_and_return(
                     {
                         (self)(context, $(
@@ -300,9 +365,147 @@ macro_rules! factory_tuple ({ $c: expr, $($param:ident)* } => {
             }
         }
     }
+
+    // Path without return
+    impl<Func, P, $($param,)*> Factory<(Path<P>, $($param,)*), ()> for Func
+    where
+        Func: Fn(Path<P>, $($param),*),
+        P: FromPath,
+        $($param: FromArma,)*
+    {
+        fn arg_types() -> Vec<&'static str> {
+            vec![$(std::any::type_name::<$param>(),)*]
+        }
+
+        fn path_types() -> Vec<&'static str> {
+            P::type_names()
+        }
+
+        fn return_type() -> Option<&'static str> {
+            None
+        }
+
+        #[allow(non_snake_case)]
+        unsafe fn call(&self, context: Context, output: *mut libc::c_char, size: libc::size_t, args: Option<*mut *mut i8>, count: Option<libc::c_int>) -> libc::c_int {
+            let path = match Path::<P>::from_segments(context.path()) {
+                Ok(path) => std::cell::Cell::new(Some(path)),
+                Err((position, e)) => {
+                    warn!("invalid path parameters: {}", e);
+                    return format!("7{}", position).parse::<libc::c_int>().unwrap();
+                }
+            };
+            // Called once, after the arguments are converted
+            let command = |$($param: $param),*| (self)(path.take().unwrap(), $($param),*);
+            Factory::<($($param,)*), ()>::call(&command, context, output, size, args, count)
+        }
+    }
+
+    // Context, path without return
+    impl<Func, P, $($param,)*> Factory<(Context, Path<P>, $($param,)*), ()> for Func
+    where
+        Func: Fn(Context, Path<P>, $($param),*),
+        P: FromPath,
+        $($param: FromArma,)*
+    {
+        fn arg_types() -> Vec<&'static str> {
+            vec![$(std::any::type_name::<$param>(),)*]
+        }
+
+        fn path_types() -> Vec<&'static str> {
+            P::type_names()
+        }
+
+        fn return_type() -> Option<&'static str> {
+            None
+        }
+
+        #[allow(non_snake_case)]
+        unsafe fn call(&self, context: Context, output: *mut libc::c_char, size: libc::size_t, args: Option<*mut *mut i8>, count: Option<libc::c_int>) -> libc::c_int {
+            let path = match Path::<P>::from_segments(context.path()) {
+                Ok(path) => std::cell::Cell::new(Some(path)),
+                Err((position, e)) => {
+                    warn!("invalid path parameters: {}", e);
+                    return format!("7{}", position).parse::<libc::c_int>().unwrap();
+                }
+            };
+            // Called once, after the arguments are converted
+            let command = |context: Context, $($param: $param),*| (self)(context, path.take().unwrap(), $($param),*);
+            Factory::<(Context, $($param,)*), ()>::call(&command, context, output, size, args, count)
+        }
+    }
+
+    // Path and return
+    impl<Func, P, $($param,)* R> Factory<(Path<P>, $($param,)*), R> for Func
+    where
+        R: IntoExtResult + 'static,
+        Func: Fn(Path<P>, $($param),*) -> R,
+        P: FromPath,
+        $($param: FromArma,)*
+    {
+        fn arg_types() -> Vec<&'static str> {
+            vec![$(std::any::type_name::<$param>(),)*]
+        }
+
+        fn path_types() -> Vec<&'static str> {
+            P::type_names()
+        }
+
+        fn return_type() -> Option<&'static str> {
+            Some(std::any::type_name::<R>())
+        }
+
+        #[allow(non_snake_case)]
+        unsafe fn call(&self, context: Context, output: *mut libc::c_char, size: libc::size_t, args: Option<*mut *mut i8>, count: Option<libc::c_int>) -> libc::c_int {
+            let path = match Path::<P>::from_segments(context.path()) {
+                Ok(path) => std::cell::Cell::new(Some(path)),
+                Err((position, e)) => {
+                    warn!("invalid path parameters: {}", e);
+                    return format!("7{}", position).parse::<libc::c_int>().unwrap();
+                }
+            };
+            // Called once, after the arguments are converted
+            let command = |$($param: $param),*| (self)(path.take().unwrap(), $($param),*);
+            Factory::<($($param,)*), R>::call(&command, context, output, size, args, count)
+        }
+    }
+
+    // Context, path and return
+    impl<Func, P, $($param,)* R> Factory<(Context, Path<P>, $($param,)*), R> for Func
+    where
+        R: IntoExtResult + 'static,
+        Func: Fn(Context, Path<P>, $($param),*) -> R,
+        P: FromPath,
+        $($param: FromArma,)*
+    {
+        fn arg_types() -> Vec<&'static str> {
+            vec![$(std::any::type_name::<$param>(),)*]
+        }
+
+        fn path_types() -> Vec<&'static str> {
+            P::type_names()
+        }
+
+        fn return_type() -> Option<&'static str> {
+            Some(std::any::type_name::<R>())
+        }
+
+        #[allow(non_snake_case)]
+        unsafe fn call(&self, context: Context, output: *mut libc::c_char, size: libc::size_t, args: Option<*mut *mut i8>, count: Option<libc::c_int>) -> libc::c_int {
+            let path = match Path::<P>::from_segments(context.path()) {
+                Ok(path) => std::cell::Cell::new(Some(path)),
+                Err((position, e)) => {
+                    warn!("invalid path parameters: {}", e);
+                    return format!("7{}", position).parse::<libc::c_int>().unwrap();
+                }
+            };
+            // Called once, after the arguments are converted
+            let command = |context: Context, $($param: $param),*| (self)(context, path.take().unwrap(), $($param),*);
+            Factory::<(Context, $($param,)*), R>::call(&command, context, output, size, args, count)
+        }
+    }
 });
 
-unsafe fn handle_output_and_return<R>(
+pub(crate) unsafe fn handle_output_and_return<R>(
     ret: R,
     output: *mut libc::c_char,
     size: libc::size_t,
@@ -1,20 +1,27 @@
 use std::sync::Arc;
 
-use crossbeam_queue::SegQueue;
-
-use crate::{IntoArma, Value};
+use crate::{
+    callback::CallbackQueue, ArmaEvent, CallbackError, CallbackSender, Caller, IntoArma, Priority,
+};
 
+#[derive(Clone)]
 /// Contains information about the current execution context
 pub struct Context {
-    pub(crate) queue: Arc<SegQueue<(String, String, Option<Value>)>>,
+    sender: CallbackSender,
     buffer_size: usize,
+    caller: Caller,
+    path: Vec<String>,
+    command: Option<Arc<str>>,
 }
 
 impl Context {
-    pub(crate) fn new(queue: Arc<SegQueue<(String, String, Option<Value>)>>) -> Self {
+    pub(crate) fn new(queue: Arc<CallbackQueue>) -> Self {
         Self {
-            queue,
+            sender: CallbackSender::new(queue),
             buffer_size: 0,
+            caller: Caller::default(),
+            path: Vec::new(),
+            command: None,
         }
     }
 
@@ -23,6 +30,60 @@ impl Context {
         self
     }
 
+    pub(crate) fn with_caller(mut self, caller: Caller) -> Self {
+        self.caller = caller;
+        self
+    }
+
+    pub(crate) fn with_path(mut self, path: Vec<String>) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// The values of the path parameters of the current command.
+    pub(crate) fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    pub(crate) fn with_command(mut self, command: Option<Arc<str>>) -> Self {
+        self.command = command;
+        self
+    }
+
+    #[must_use]
+    /// Returns the path the current command was added with, such as `vehicle:{id}:fuel`.
+    /// Returns `None` if no command matches the call.
+    pub fn command(&self) -> Option<&str> {
+        self.command.as_deref()
+    }
+
+    #[must_use]
+    /// Returns information about the caller of the current command.
+    pub const fn caller(&self) -> &Caller {
+        &self.caller
+    }
+
+    pub(crate) fn with_call_id(mut self, call_id: u64) -> Self {
+        self.sender = self.sender.with_call_id(call_id);
+        self
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn with_span(mut self, span: tracing::Span) -> Self {
+        self.sender = self.sender.with_span(span);
+        self
+    }
+
+    #[must_use]
+    /// Returns the id of the current call, unique within the extension.
+    /// Returns `None` for contexts that were not created by a call.
+    ///
+    /// See [`ExtensionBuilder::correlate_callbacks`](crate::ExtensionBuilder::correlate_callbacks)
+    /// to include the id in callbacks sent during the call.
+    pub const fn call_id(&self) -> Option<u64> {
+        self.sender.call_id()
+    }
+
     #[must_use]
     /// Returns the length in bytes of the output buffer.
     /// This is the maximum size of the data that can be returned by the extension.
@@ -34,14 +95,73 @@ impl Context {
         }
     }
 
+    #[must_use]
+    /// Returns a sender for the extension's callbacks that outlives this context.
+    pub fn callback_sender(&self) -> CallbackSender {
+        self.sender.clone()
+    }
+
     /// Sends a callback into Arma
     /// <https://community.bistudio.com/wiki/Arma_3:_Mission_Event_Handlers#ExtensionCallback>
+    ///
+    /// Data larger than Arma's callback limit is sent in multiple parts,
+    /// see the README for how to reassemble them in SQF.
+    /// A callback discarded because the queue is full is counted in
+    /// [`Extension::callback_stats`](crate::Extension::callback_stats),
+    /// use [`Context::try_callback`] to handle it.
     pub fn callback<V>(&self, name: &str, func: &str, data: Option<V>)
     where
         V: IntoArma,
     {
-        self.queue
-            .push((name.to_string(), func.to_string(), Some(data.into())));
+        let _ = self.sender.callback(name, func, data);
+    }
+
+    /// Sends a callback into Arma, like [`Context::callback`].
+    ///
+    /// # Errors
+    /// Will return an error if the callback queue is full and the extension was built
+    /// with [`OverflowPolicy::Error`](crate::OverflowPolicy::Error).
+    pub fn try_callback<V>(
+        &self,
+        name: &str,
+        func: &str,
+        data: Option<V>,
+    ) -> Result<(), CallbackError>
+    where
+        V: IntoArma,
+    {
+        self.sender.callback(name, func, data)
+    }
+
+    /// Sends an event into Arma as a callback
+    ///
+    /// # Errors
+    /// Will return an error if the callback queue is full and the extension was built
+    /// with [`OverflowPolicy::Error`](crate::OverflowPolicy::Error).
+    pub fn emit<E>(&self, event: E) -> Result<(), CallbackError>
+    where
+        E: ArmaEvent,
+    {
+        self.sender.emit(event)
+    }
+
+    /// Sends a callback into Arma ahead of callbacks with a lower priority.
+    ///
+    /// # Errors
+    /// Will return an error if the callback queue is full and the extension was built
+    /// with [`OverflowPolicy::Error`](crate::OverflowPolicy::Error).
+    pub fn callback_with_priority<V>(
+        &self,
+        name: &str,
+        func: &str,
+        data: Option<V>,
+        priority: Priority,
+    ) -> Result<(), CallbackError>
+    where
+        V: IntoArma,
+    {
+        self.sender
+            .callback_with_priority(name, func, data, priority)
     }
 }
 
@@ -51,13 +171,22 @@ mod tests {
 
     #[test]
     fn context_buffer_len_zero() {
-        let ctx = Context::new(Arc::new(SegQueue::new()));
+        let ctx = Context::new(Arc::new(CallbackQueue::default()));
         assert_eq!(ctx.buffer_len(), 0);
     }
 
     #[test]
     fn context_buffer_len() {
-        let ctx = Context::new(Arc::new(SegQueue::new())).with_buffer_size(100);
+        let ctx = Context::new(Arc::new(CallbackQueue::default())).with_buffer_size(100);
         assert_eq!(ctx.buffer_len(), 99);
     }
+
+    #[test]
+    fn context_call_id() {
+        let ctx = Context::new(Arc::new(CallbackQueue::default()));
+        assert_eq!(ctx.call_id(), None);
+        let ctx = ctx.with_call_id(3);
+        assert_eq!(ctx.call_id(), Some(3));
+        assert_eq!(ctx.callback_sender().call_id(), Some(3));
+    }
 }
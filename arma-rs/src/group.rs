@@ -1,16 +1,50 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, RwLock},
+};
 
 use crate::{
-    command::{fn_handler, Factory, Handler},
-    Context,
+    command::{fn_handler, handle_output_and_return, Factory, Handler},
+    ext_result::IntoExtResult,
+    middleware::{self, Call, Middleware},
+    route::Route,
+    router::Mounts,
+    AutoCommand, CommandDoc, CommandInfo, Context, Guard, RateLimit,
 };
 
-#[derive(Default)]
+type Fallback = Box<
+    dyn Fn(Context, &str, Vec<String>, *mut libc::c_char, libc::size_t) -> libc::c_int
+        + Send
+        + Sync,
+>;
+
 /// A group of commands.
-/// Called from Arma using `[group]:[command]`.
+/// Called from Arma using `[group]:[command]`, or the separator set with
+/// [`ExtensionBuilder::separator`](crate::ExtensionBuilder::separator).
 pub struct Group {
     commands: HashMap<String, Box<Handler>>,
     children: HashMap<String, Self>,
+    middleware: Vec<Box<dyn Middleware>>,
+    guards: HashMap<String, Guard>,
+    limits: HashMap<String, RateLimit>,
+    docs: HashMap<String, CommandDoc>,
+    duplicates: Vec<TreeError>,
+    fallback: Option<Fallback>,
+    names: Vec<String>,
+    routes: Vec<Route>,
+    paths: HashMap<String, Arc<str>>,
+    aliases: HashMap<String, String>,
+    deprecated: HashMap<String, String>,
+    warned: Mutex<HashSet<(String, Option<String>)>>,
+    case_insensitive: bool,
+    separator: char,
+    mounts: Option<Arc<RwLock<Mounts>>>,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Group {
@@ -20,18 +54,46 @@ impl Group {
         Self {
             commands: HashMap::new(),
             children: HashMap::new(),
+            middleware: Vec::new(),
+            guards: HashMap::new(),
+            limits: HashMap::new(),
+            docs: HashMap::new(),
+            duplicates: Vec::new(),
+            fallback: None,
+            names: Vec::new(),
+            routes: Vec::new(),
+            paths: HashMap::new(),
+            aliases: HashMap::new(),
+            deprecated: HashMap::new(),
+            warned: Mutex::new(HashSet::new()),
+            case_insensitive: false,
+            separator: ':',
+            mounts: None,
         }
     }
 
     #[inline]
     /// Add a command to the group
+    ///
+    /// The name can contain the separator and parameters in braces, such as `vehicle:{id}:fuel`,
+    /// taken by the command as a [`Path`](crate::Path).
+    /// Routes are matched in the order they were added, after commands and groups matching by name.
+    /// The handler must be `Send` and `Sync`, groups can be mounted from any thread with a [`Router`](crate::Router).
+    /// A route starting with the name of a subgroup is never matched, it is reported as
+    /// [`TreeError::Shadowed`] by [`ExtensionBuilder::try_finish`](crate::ExtensionBuilder::try_finish).
     pub fn command<S, F, I, R>(mut self, name: S, handler: F) -> Self
     where
         S: Into<String>,
-        F: Factory<I, R> + 'static,
+        F: Factory<I, R> + Send + Sync + 'static,
     {
-        self.commands
-            .insert(name.into(), Box::new(fn_handler(handler)));
+        let name = name.into();
+        if self.commands.contains_key(&name) {
+            self.duplicates
+                .push(TreeError::DuplicateCommand(name.clone()));
+        } else {
+            self.names.push(name.clone());
+        }
+        self.commands.insert(name, Box::new(fn_handler(handler)));
         self
     }
 
@@ -41,10 +103,319 @@ impl Group {
     where
         S: Into<String>,
     {
-        self.children.insert(name.into(), child);
+        let name = name.into();
+        if self.children.contains_key(&name) {
+            self.duplicates
+                .push(TreeError::DuplicateGroup(name.clone()));
+        }
+        self.children.insert(name, child);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Makes `alias` call the command `command` of the group, such as the old name of a renamed command.
+    /// An alias with the name of a command of the group is reported as [`TreeError::DuplicateCommand`].
+    pub fn alias<S1, S2>(mut self, alias: S1, command: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        let alias = alias.into();
+        if self.aliases.contains_key(&alias) {
+            self.duplicates
+                .push(TreeError::DuplicateCommand(alias.clone()));
+        }
+        self.aliases.insert(alias, command.into());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Marks the command or alias `name` of the group as deprecated.
+    /// The first call from each caller logs a warning with `note`, such as `"use hello:english"`.
+    pub fn deprecated<S1, S2>(mut self, name: S1, note: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        self.deprecated.insert(name.into(), note.into());
         self
     }
 
+    #[inline]
+    #[must_use]
+    /// Matches the names of commands, aliases and subgroups ignoring ASCII case,
+    /// for this group and its subgroups.
+    /// Calls are matched by the exact name first, only calls with a different case are slower.
+    pub const fn case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Handles calls that match no command or subgroup of the group.
+    ///
+    /// The handler receives the unmatched rest of the path, `table:get` for a call to
+    /// `db:table:get` on the group `db`, and the arguments as sent by Arma, strings are still quoted.
+    /// Subgroups without a fallback return code 1 for unknown commands, as without a fallback.
+    pub fn fallback<F, R>(mut self, handler: F) -> Self
+    where
+        F: Fn(Context, &str, Vec<String>) -> R + Send + Sync + 'static,
+        R: IntoExtResult + 'static,
+    {
+        self.fallback = Some(Box::new(move |context, function, args, output, size| {
+            let result = handler(context, function, args);
+            unsafe { handle_output_and_return(result, output, size) }
+        }));
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Add a middleware that runs around every command of the group and its subgroups.
+    pub fn middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware,
+    {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only allows callers allowed by `guard` to run the commands of the group and its subgroups.
+    pub fn guard(self, guard: Guard) -> Self {
+        self.middleware(guard)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only allows callers allowed by `guard` to run the command `name` of the group.
+    pub fn guard_command<S>(mut self, name: S, guard: Guard) -> Self
+    where
+        S: Into<String>,
+    {
+        self.guards.insert(name.into(), guard);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Limits how often the command `name` of the group can be called.
+    pub fn rate_limit_command<S>(mut self, name: S, limit: RateLimit) -> Self
+    where
+        S: Into<String>,
+    {
+        self.limits.insert(name.into(), limit);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Documents the command `name` of the group.
+    pub fn document<S>(mut self, name: S, doc: CommandDoc) -> Self
+    where
+        S: Into<String>,
+    {
+        self.docs.insert(name.into(), doc);
+        self
+    }
+
+    /// Adds a command registered with `#[command]` to the subgroup at `path`.
+    pub(crate) fn auto_command(mut self, path: &[&str], command: &AutoCommand) -> Self {
+        match path.split_first() {
+            Some((name, rest)) => {
+                let child = self.children.remove(*name).unwrap_or_default();
+                self.children
+                    .insert((*name).to_string(), child.auto_command(rest, command));
+                self
+            }
+            None if self.commands.contains_key(command.name()) => {
+                self.duplicates
+                    .push(TreeError::DuplicateCommand(command.name().to_string()));
+                self
+            }
+            None => command.register(self),
+        }
+    }
+
+    /// Sets the separator of this group and its subgroups, parsing the routes with it,
+    /// and enables case insensitive matching in subgroups of case insensitive groups.
+    /// `prefix` is the path of the group followed by the separator, the full paths of its commands
+    /// are computed once here instead of on every call.
+    pub(crate) fn configure(&mut self, prefix: &str, separator: char, case_insensitive: bool) {
+        self.separator = separator;
+        self.case_insensitive |= case_insensitive;
+        self.routes = self
+            .names
+            .iter()
+            .filter(|name| Route::is_route(name, separator))
+            .map(|name| Route::new(name.clone(), separator))
+            .collect();
+        self.paths = self
+            .commands
+            .keys()
+            .map(String::as_str)
+            .chain(self.fallback.as_ref().map(|_| "*"))
+            .map(|name| (name.to_string(), Arc::from(format!("{}{}", prefix, name))))
+            .collect();
+        for (name, group) in &mut self.children {
+            group.configure(
+                &format!("{}{}{}", prefix, name, separator),
+                separator,
+                self.case_insensitive,
+            );
+        }
+    }
+
+    /// Routes calls matching no subgroup to the groups in `mounts`,
+    /// mounting the groups mounted before the extension was built and returning their problems.
+    pub(crate) fn set_mounts(&mut self, mounts: Arc<RwLock<Mounts>>) -> Vec<TreeError> {
+        let routes = self
+            .routes
+            .iter()
+            .filter_map(|route| {
+                let (first, _) = route.name().split_once(self.separator)?;
+                let parameter = first.starts_with('{') && first.ends_with('}');
+                (!parameter).then(|| (first.to_string(), route.name().to_string()))
+            })
+            .collect();
+        let errors = mounts.write().unwrap().start(
+            self.separator,
+            self.case_insensitive,
+            self.children.keys().cloned().collect(),
+            routes,
+        );
+        self.mounts = Some(mounts);
+        errors
+    }
+
+    /// Adds the problems of this group and its subgroups to `errors`,
+    /// with their paths starting with `prefix`.
+    pub(crate) fn validate(&self, prefix: &str, errors: &mut Vec<TreeError>) {
+        errors.extend(self.duplicates.iter().map(|error| error.prefixed(prefix)));
+        let mut names = self
+            .commands
+            .keys()
+            .map(|name| (name, true))
+            .chain(self.children.keys().map(|name| (name, false)))
+            .collect::<Vec<_>>();
+        names.sort();
+        // Names matching the same calls, differing only in case or in the names of their
+        // parameters, are duplicates. Only one of them would ever be called.
+        let mut seen = HashSet::new();
+        for (name, command) in names {
+            let path = format!("{}{}", prefix, name);
+            if name.split(self.separator).any(str::is_empty) {
+                errors.push(TreeError::EmptyName(path));
+            } else if !command && name.contains(self.separator) {
+                errors.push(TreeError::Separator(path));
+            } else if command && self.shadowed(name) {
+                errors.push(TreeError::Shadowed(path));
+            } else if !seen.insert((self.key(name), command)) {
+                errors.push(if command {
+                    TreeError::DuplicateCommand(path)
+                } else {
+                    TreeError::DuplicateGroup(path)
+                });
+            }
+        }
+        let commands = self
+            .commands
+            .keys()
+            .map(|name| self.key(name))
+            .collect::<HashSet<_>>();
+        let mut aliases = self.aliases.iter().collect::<Vec<_>>();
+        aliases.sort();
+        let mut seen = HashSet::new();
+        for (alias, command) in aliases {
+            let path = format!("{}{}", prefix, alias);
+            let key = self.key(alias);
+            if commands.contains(&key) || !seen.insert(key) {
+                errors.push(TreeError::DuplicateCommand(path));
+            } else if !self.commands.contains_key(command) {
+                errors.push(TreeError::UnknownAlias(path));
+            }
+        }
+        let mut children = self.children.iter().collect::<Vec<_>>();
+        children.sort_by_key(|(name, _)| *name);
+        for (name, group) in children {
+            group.validate(&format!("{}{}{}", prefix, name, self.separator), errors);
+        }
+    }
+
+    /// Returns the calls `name` matches in this group, see [`Route::key`].
+    fn key(&self, name: &str) -> String {
+        Route::key(name, self.separator, self.case_insensitive)
+    }
+
+    /// Returns if the first segment of the command `name` is a subgroup, which handles its calls.
+    fn shadowed(&self, name: &str) -> bool {
+        match name.split_once(self.separator) {
+            Some((first, _)) => self.children.keys().any(|child| {
+                child == first || (self.case_insensitive && child.eq_ignore_ascii_case(first))
+            }),
+            None => false,
+        }
+    }
+
+    /// Adds the commands of this group and its subgroups to `commands`,
+    /// with their paths starting with `prefix`.
+    /// `errors` are the codes returned by the middleware of the parent groups.
+    pub(crate) fn commands(
+        &self,
+        prefix: &str,
+        errors: &[(libc::c_int, String)],
+        commands: &mut Vec<CommandInfo>,
+    ) {
+        let mut errors = errors.to_vec();
+        errors.extend(
+            self.middleware
+                .iter()
+                .flat_map(|middleware| middleware.errors()),
+        );
+        for (name, handler) in &self.commands {
+            let mut command_errors = errors.clone();
+            if let Some(guard) = self.guards.get(name) {
+                command_errors.extend(guard.errors());
+            }
+            if let Some(limit) = self.limits.get(name) {
+                command_errors.extend(limit.errors());
+            }
+            let mut aliases = self
+                .aliases
+                .iter()
+                .filter(|(_, command)| *command == name)
+                .map(|(alias, _)| format!("{}{}", prefix, alias))
+                .collect::<Vec<_>>();
+            aliases.sort();
+            commands.push(
+                CommandInfo::new(
+                    format!("{}{}", prefix, name),
+                    &handler.arg_types,
+                    handler.return_type,
+                    self.docs.get(name).cloned().unwrap_or_default(),
+                )
+                .with_path_params(&handler.path_types)
+                .with_aliases(aliases)
+                .with_deprecated(self.deprecated.get(name).cloned())
+                .with_errors(&command_errors),
+            );
+        }
+        for (name, group) in &self.children {
+            group.commands(
+                &format!("{}{}{}", prefix, name, self.separator),
+                &errors,
+                commands,
+            );
+        }
+        if let Some(mounts) = &self.mounts {
+            mounts.read().unwrap().commands(prefix, &errors, commands);
+        }
+    }
+
     pub(crate) fn handle(
         &self,
         context: Context,
@@ -54,14 +425,467 @@ impl Group {
         args: Option<*mut *mut i8>,
         count: Option<libc::c_int>,
     ) -> libc::c_int {
-        if let Some((group, function)) = function.split_once(':') {
-            self.children.get(group).map_or(1, |group| {
-                group.handle(context, function, output, size, args, count)
+        self.dispatch(context, function, function, output, size, args, count)
+    }
+
+    /// Runs the command at `function`, relative to this group, wrapped by the group's middleware.
+    /// `path` is the full path of the command.
+    #[allow(clippy::too_many_arguments)]
+    fn dispatch(
+        &self,
+        context: Context,
+        path: &str,
+        function: &str,
+        output: *mut libc::c_char,
+        size: libc::size_t,
+        args: Option<*mut *mut i8>,
+        count: Option<libc::c_int>,
+    ) -> libc::c_int {
+        if self.middleware.is_empty() {
+            return self.route(context, path, function, output, size, args, count);
+        }
+        // Middleware can read the command, such as a rate limit per command.
+        let context = match context.command() {
+            Some(_) => context,
+            None => {
+                let command = self.resolve(function);
+                context.with_command(command)
+            }
+        };
+        unsafe {
+            self.wrap(
+                context.clone(),
+                path,
+                output,
+                size,
+                args,
+                count,
+                |output, size, args, count| {
+                    self.route(context, path, function, output, size, args, count)
+                },
+            )
+        }
+    }
+
+    /// Runs `inner` wrapped by the group's middleware, such as the built-in commands of the extension.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) unsafe fn wrap<C>(
+        &self,
+        context: Context,
+        path: &str,
+        output: *mut libc::c_char,
+        size: libc::size_t,
+        args: Option<*mut *mut i8>,
+        count: Option<libc::c_int>,
+        inner: C,
+    ) -> libc::c_int
+    where
+        C: FnOnce(
+            *mut libc::c_char,
+            libc::size_t,
+            Option<*mut *mut i8>,
+            Option<libc::c_int>,
+        ) -> libc::c_int,
+    {
+        if self.middleware.is_empty() {
+            return inner(output, size, args, count);
+        }
+        let call = Call::new(path, middleware::read_args(args, count), context);
+        middleware::run(&self.middleware, call, output, size, args, count, inner)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn route(
+        &self,
+        context: Context,
+        path: &str,
+        function: &str,
+        output: *mut libc::c_char,
+        size: libc::size_t,
+        args: Option<*mut *mut i8>,
+        count: Option<libc::c_int>,
+    ) -> libc::c_int {
+        if let Some((group, rest)) = function.split_once(self.separator) {
+            if let Some((_, child)) = self.child(group) {
+                return child.dispatch(context, path, rest, output, size, args, count);
+            }
+            // The group is cloned out of the mounts, so it can be unmounted while it runs.
+            let mounted = self
+                .mounts
+                .as_ref()
+                .and_then(|mounts| mounts.read().unwrap().get(group));
+            if let Some((_, mounted)) = mounted {
+                return mounted.dispatch(context, path, rest, output, size, args, count);
+            }
+        } else if !Route::is_route(function, self.separator) {
+            if let Some((called, name, handler)) = self.find(function) {
+                self.warn_deprecated(called, name, path, &context);
+                return self.call(name, handler, context, path, output, size, args, count);
+            }
+        }
+        for route in &self.routes {
+            if let Some(captures) = route.matches(function, self.separator, self.case_insensitive) {
+                self.warn_deprecated(route.name(), route.name(), path, &context);
+                let handler = &self.commands[route.name()];
+                let context = context.with_path(captures);
+                return self.call(
+                    route.name(),
+                    handler,
+                    context,
+                    path,
+                    output,
+                    size,
+                    args,
+                    count,
+                );
+            }
+        }
+        self.fallback.as_ref().map_or(1, |fallback| {
+            let args = unsafe { middleware::read_args(args, count) };
+            fallback(
+                self.with_command(context, "*"),
+                function,
+                args,
+                output,
+                size,
+            )
+        })
+    }
+
+    /// Returns the full path of the command handling `function`, relative to this group,
+    /// as it was added: `vehicle:{id}:fuel` for a call to `vehicle:42:fuel`.
+    /// Calls handled by a fallback return the path of its group followed by `*`.
+    pub(crate) fn resolve(&self, function: &str) -> Option<Arc<str>> {
+        let separator = self.separator;
+        if let Some((group, rest)) = function.split_once(separator) {
+            if let Some((_, child)) = self.child(group) {
+                return child.resolve(rest);
+            }
+            let mounted = self
+                .mounts
+                .as_ref()
+                .and_then(|mounts| mounts.read().unwrap().get(group));
+            if let Some((_, mounted)) = mounted {
+                return mounted.resolve(rest);
+            }
+        } else if !Route::is_route(function, separator) {
+            if let Some((_, name, _)) = self.find(function) {
+                return self.paths.get(name).cloned();
+            }
+        }
+        let name = self
+            .routes
+            .iter()
+            .find(|route| {
+                route
+                    .matches(function, separator, self.case_insensitive)
+                    .is_some()
             })
-        } else if let Some(handler) = self.commands.get(function) {
-            (handler.handler)(context, output, size, args, count)
-        } else {
-            1
+            .map_or("*", Route::name);
+        self.paths.get(name).cloned()
+    }
+
+    /// Sets the command of `context` to the command `name` of this group, unless it is already set.
+    fn with_command(&self, context: Context, name: &str) -> Context {
+        match context.command() {
+            Some(_) => context,
+            None => context.with_command(self.paths.get(name).cloned()),
         }
     }
+
+    /// Finds the subgroup `name`, returning the name it was added with.
+    fn child(&self, name: &str) -> Option<(&str, &Self)> {
+        self.children
+            .get_key_value(name)
+            .or_else(|| {
+                if self.case_insensitive {
+                    self.children
+                        .iter()
+                        .find(|(child, _)| child.eq_ignore_ascii_case(name))
+                } else {
+                    None
+                }
+            })
+            .map(|(name, group)| (name.as_str(), group))
+    }
+
+    /// Finds the command called `function`, directly or through an alias.
+    /// Returns the name that was matched, the name of the command and its handler.
+    fn find(&self, function: &str) -> Option<(&str, &str, &Handler)> {
+        let resolve = |called: &str| -> Option<(&str, &str, &Handler)> {
+            if let Some((name, handler)) = self.commands.get_key_value(called) {
+                return Some((name, name, handler));
+            }
+            let (alias, name) = self.aliases.get_key_value(called)?;
+            let (name, handler) = self.commands.get_key_value(name)?;
+            Some((alias, name, handler))
+        };
+        resolve(function).or_else(|| {
+            if !self.case_insensitive {
+                return None;
+            }
+            self.commands
+                .keys()
+                .chain(self.aliases.keys())
+                .find(|name| name.eq_ignore_ascii_case(function))
+                .and_then(|name| resolve(name))
+        })
+    }
+
+    /// Logs a warning the first time each caller calls a deprecated command.
+    fn warn_deprecated(&self, called: &str, name: &str, path: &str, context: &Context) {
+        let Some(note) = self
+            .deprecated
+            .get(called)
+            .or_else(|| self.deprecated.get(name))
+        else {
+            return;
+        };
+        let caller = context.caller().steam_id().map(ToString::to_string);
+        if self
+            .warned
+            .lock()
+            .unwrap()
+            .insert((path.to_string(), caller.clone()))
+        {
+            match caller {
+                Some(caller) => warn!("{} is deprecated, called by {}: {}", path, caller, note),
+                None => warn!("{} is deprecated: {}", path, note),
+            }
+        }
+    }
+
+    /// Runs the command `name` of the group, if its guard allows the caller and it is within its rate limit.
+    #[allow(clippy::too_many_arguments)]
+    fn call(
+        &self,
+        name: &str,
+        handler: &Handler,
+        context: Context,
+        path: &str,
+        output: *mut libc::c_char,
+        size: libc::size_t,
+        args: Option<*mut *mut i8>,
+        count: Option<libc::c_int>,
+    ) -> libc::c_int {
+        let context = self.with_command(context, name);
+        if let Some(guard) = self.guards.get(name) {
+            if let Err(code) = guard.check(path, context.caller()) {
+                return code;
+            }
+        }
+        if let Some(limit) = self.limits.get(name) {
+            if let Err(code) = limit.check(&context) {
+                return code;
+            }
+        }
+        (handler.handler)(context, output, size, args, count)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A problem in the commands of an extension, returned by
+/// [`ExtensionBuilder::try_finish`](crate::ExtensionBuilder::try_finish).
+///
+/// A command and a subgroup can share a name, `group:name` calls the command
+/// and `group:name:command` a command of the subgroup. A command named
+/// `name:command` is reported as [`TreeError::Shadowed`], the subgroup handles its calls.
+pub enum TreeError {
+    /// A command was added twice at the path, only the last one is kept,
+    /// or it matches the same calls as another command or alias, differing only in case
+    /// in a case insensitive group or in the names of its parameters.
+    DuplicateCommand(String),
+    /// A group was added twice at the path, only the last one is kept,
+    /// or its name differs only in case from another group in a case insensitive group.
+    DuplicateGroup(String),
+    /// A group name at the path contains the separator and can never be called.
+    Separator(String),
+    /// A command or group at the path has an empty name, or a command an empty segment.
+    EmptyName(String),
+    /// An alias at the path calls a command that is not in its group.
+    UnknownAlias(String),
+    /// A command at the path is handled by a built-in command of arma-rs or by a subgroup
+    /// matching the start of its name, and can never be called.
+    Shadowed(String),
+}
+
+impl TreeError {
+    fn prefixed(&self, prefix: &str) -> Self {
+        match self {
+            Self::DuplicateCommand(path) => Self::DuplicateCommand(format!("{}{}", prefix, path)),
+            Self::DuplicateGroup(path) => Self::DuplicateGroup(format!("{}{}", prefix, path)),
+            Self::Separator(path) => Self::Separator(format!("{}{}", prefix, path)),
+            Self::EmptyName(path) => Self::EmptyName(format!("{}{}", prefix, path)),
+            Self::UnknownAlias(path) => Self::UnknownAlias(format!("{}{}", prefix, path)),
+            Self::Shadowed(path) => Self::Shadowed(format!("{}{}", prefix, path)),
+        }
+    }
+}
+
+impl std::fmt::Display for TreeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateCommand(path) => write!(f, "command {} was added more than once", path),
+            Self::DuplicateGroup(path) => write!(f, "group {} was added more than once", path),
+            Self::Separator(path) => {
+                write!(f, "{} contains the separator and can not be called", path)
+            }
+            Self::EmptyName(path) => write!(f, "{} has an empty name", path),
+            Self::UnknownAlias(path) => write!(f, "alias {} calls an unknown command", path),
+            Self::Shadowed(path) => {
+                write!(
+                    f,
+                    "{} is handled by a built-in command or a subgroup and can not be called",
+                    path
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TreeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{callback::CallbackQueue, Caller};
+
+    #[test]
+    fn deprecated_once_per_caller() {
+        let group = Group::new()
+            .command("new", || {})
+            .alias("old", "new")
+            .deprecated("old", "use new");
+        let context = Context::new(std::sync::Arc::new(CallbackQueue::default()));
+        let player = context
+            .clone()
+            .with_caller(Caller::new().with_steam_id("76561198000000001"));
+        group.warn_deprecated("new", "new", "new", &context);
+        assert!(group.warned.lock().unwrap().is_empty());
+        group.warn_deprecated("old", "new", "old", &context);
+        group.warn_deprecated("old", "new", "old", &context);
+        group.warn_deprecated("old", "new", "old", &player);
+        assert_eq!(group.warned.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn duplicate_auto_commands() {
+        let first = AutoCommand::new("a", "b", |group| group.command("b", || "first"));
+        let second = AutoCommand::new("a", "b", |group| group.command("b", || "second"));
+        let group = Group::new()
+            .auto_command(&first.groups(), &first)
+            .auto_command(&second.groups(), &second);
+        let mut errors = Vec::new();
+        group.validate("", &mut errors);
+        assert_eq!(errors, vec![TreeError::DuplicateCommand("a:b".to_string())]);
+    }
+
+    #[test]
+    fn alias_collisions() {
+        for group in [
+            Group::new()
+                .alias("x", "y")
+                .command("x", || {})
+                .command("y", || {}),
+            Group::new()
+                .command("x", || {})
+                .command("y", || {})
+                .alias("x", "y"),
+        ] {
+            let mut errors = Vec::new();
+            group.validate("a:", &mut errors);
+            assert_eq!(errors, vec![TreeError::DuplicateCommand("a:x".to_string())]);
+        }
+    }
+
+    #[test]
+    fn shadowed_commands() {
+        let mut group = Group::new()
+            .command("vehicle:{id}", || {})
+            .command("Vehicle:list", || {})
+            .command("vehicles:list", || {})
+            .group("vehicle", Group::new().command("list", || {}))
+            .case_insensitive();
+        group.configure("", ':', true);
+        let mut errors = Vec::new();
+        group.validate("", &mut errors);
+        assert_eq!(
+            errors,
+            vec![
+                TreeError::Shadowed("Vehicle:list".to_string()),
+                TreeError::Shadowed("vehicle:{id}".to_string()),
+            ]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "Vehicle:list is handled by a built-in command or a subgroup and can not be called"
+        );
+    }
+
+    #[test]
+    fn matching_names() {
+        let mut group = Group::new()
+            .command("Hello", || {})
+            .command("hello", || {})
+            .command("vehicle:{id}:fuel", || {})
+            .command("vehicle:{vehicle}:fuel", || {})
+            .command("vehicle:{id}:Fuel", || {})
+            .group("DB", Group::new())
+            .group("db", Group::new())
+            .alias("HELLO", "Hello")
+            .alias("hi", "hello")
+            .alias("Hi", "hello")
+            .case_insensitive();
+        group.configure("", ':', false);
+        let mut errors = Vec::new();
+        group.validate("", &mut errors);
+        assert_eq!(
+            errors,
+            vec![
+                TreeError::DuplicateGroup("db".to_string()),
+                TreeError::DuplicateCommand("hello".to_string()),
+                TreeError::DuplicateCommand("vehicle:{id}:fuel".to_string()),
+                TreeError::DuplicateCommand("vehicle:{vehicle}:fuel".to_string()),
+                TreeError::DuplicateCommand("HELLO".to_string()),
+                TreeError::DuplicateCommand("hi".to_string()),
+            ]
+        );
+
+        let mut group = Group::new()
+            .command("Hello", || {})
+            .command("hello", || {})
+            .command("a:{x}", || {})
+            .command("a:{y}", || {});
+        group.configure("", ':', false);
+        let mut errors = Vec::new();
+        group.validate("", &mut errors);
+        assert_eq!(
+            errors,
+            vec![TreeError::DuplicateCommand("a:{y}".to_string())]
+        );
+    }
+
+    #[test]
+    fn resolve() {
+        let mut group = Group::new()
+            .command("hello", || {})
+            .alias("hi", "hello")
+            .command("vehicle:{id}:fuel", || {})
+            .group(
+                "DB",
+                Group::new()
+                    .command("get", || {})
+                    .fallback(|_: Context, _: &str, _: Vec<String>| "fallback"),
+            )
+            .case_insensitive();
+        group.configure("", ':', false);
+        assert_eq!(group.resolve("hi").as_deref(), Some("hello"));
+        assert_eq!(
+            group.resolve("vehicle:42:fuel").as_deref(),
+            Some("vehicle:{id}:fuel")
+        );
+        assert_eq!(group.resolve("db:GET").as_deref(), Some("DB:get"));
+        assert_eq!(group.resolve("db:tables").as_deref(), Some("DB:*"));
+        assert_eq!(group.resolve("missing"), None);
+    }
 }
@@ -0,0 +1,184 @@
+//! Restricts commands to trusted callers.
+
+use std::collections::HashSet;
+
+use crate::{Call, Caller, Middleware};
+
+/// The code returned for calls rejected by a [`Guard`].
+pub const UNAUTHORIZED: libc::c_int = 6;
+
+/// Decides which callers may run a command.
+///
+/// Added with [`Group::guard`](crate::Group::guard) a guard protects every command of a group,
+/// added with [`Group::guard_command`](crate::Group::guard_command) it protects a single command.
+/// Rejected calls return [`UNAUTHORIZED`] without running the command.
+///
+/// ```
+/// # use arma_rs::{Extension, Group, Guard};
+/// Extension::build()
+///     .group(
+///         "admin",
+///         Group::new().guard(Guard::server().or(Guard::steam_ids(["76561198000000001"]))),
+///     )
+///     .finish();
+/// ```
+pub struct Guard {
+    allows: Box<dyn Fn(&Caller) -> bool + Send + Sync>,
+}
+
+impl Guard {
+    #[must_use]
+    /// Allows callers for which `allows` returns true.
+    pub fn new<F>(allows: F) -> Self
+    where
+        F: Fn(&Caller) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            allows: Box::new(allows),
+        }
+    }
+
+    #[must_use]
+    /// Allows calls made by the server: calls without a player Steam ID,
+    /// that were not remote executed by a client.
+    ///
+    /// Calls from unknown callers are rejected, Arma only sends the caller since Arma 3 2.11.
+    /// Combine with [`Guard::unknown`] to allow them on older versions of Arma,
+    /// where players can not be told apart from the server.
+    pub fn server() -> Self {
+        Self::new(|caller| {
+            caller.is_known()
+                && caller.steam_id().is_none()
+                && caller.remote_owner().is_none_or(|owner| owner == 2)
+        })
+    }
+
+    #[must_use]
+    /// Allows calls from callers Arma sent no information about, see [`Caller::is_known`].
+    pub fn unknown() -> Self {
+        Self::new(|caller| !caller.is_known())
+    }
+
+    #[must_use]
+    /// Allows calls from players with one of the given Steam IDs.
+    pub fn steam_ids<I, S>(ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let ids = ids.into_iter().map(Into::into).collect::<HashSet<String>>();
+        Self::new(move |caller| caller.steam_id().is_some_and(|id| ids.contains(id)))
+    }
+
+    #[must_use]
+    /// Allows calls made from files under one of the given paths, such as `x\my_mod\`.
+    /// Paths are compared ignoring case and the leading backslash.
+    pub fn file_sources<I, S>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let paths = paths
+            .into_iter()
+            .map(|path| normalize(&path.into()))
+            .collect::<Vec<_>>();
+        Self::new(move |caller| {
+            caller.file_source().is_some_and(|file| {
+                let file = normalize(file);
+                paths.iter().any(|path| file.starts_with(path.as_str()))
+            })
+        })
+    }
+
+    #[must_use]
+    /// Allows callers allowed by either guard.
+    pub fn or(self, other: Self) -> Self {
+        Self::new(move |caller| self.allows(caller) || other.allows(caller))
+    }
+
+    #[must_use]
+    /// Allows callers allowed by both guards.
+    pub fn and(self, other: Self) -> Self {
+        Self::new(move |caller| self.allows(caller) && other.allows(caller))
+    }
+
+    #[must_use]
+    /// Returns if the guard allows `caller`.
+    pub fn allows(&self, caller: &Caller) -> bool {
+        (self.allows)(caller)
+    }
+
+    /// Checks a call to the command at `path`.
+    pub(crate) fn check(&self, path: &str, caller: &Caller) -> Result<(), libc::c_int> {
+        if self.allows(caller) {
+            Ok(())
+        } else {
+            warn!("rejected call to {} from {:?}", path, caller);
+            Err(UNAUTHORIZED)
+        }
+    }
+}
+
+impl Middleware for Guard {
+    fn before(&self, call: &mut Call) -> Result<(), libc::c_int> {
+        self.check(call.path(), call.context().caller())
+    }
+
+    fn errors(&self) -> Vec<(libc::c_int, String)> {
+        vec![(UNAUTHORIZED, "Unauthorized".to_string())]
+    }
+}
+
+fn normalize(path: &str) -> String {
+    path.trim_start_matches('\\').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: &str = "76561198000000001";
+
+    #[test]
+    fn server() {
+        let guard = Guard::server();
+        assert!(guard.allows(&Caller::new()));
+        assert!(guard.allows(&Caller::new().with_remote_owner(2)));
+        assert!(!guard.allows(&Caller::new().with_remote_owner(3)));
+        assert!(!guard.allows(&Caller::new().with_steam_id(PLAYER)));
+        assert!(!guard.allows(&Caller::default()));
+        assert!(Guard::server()
+            .or(Guard::unknown())
+            .allows(&Caller::default()));
+    }
+
+    #[test]
+    fn steam_ids() {
+        let guard = Guard::steam_ids([PLAYER]);
+        assert!(guard.allows(&Caller::new().with_steam_id(PLAYER)));
+        assert!(!guard.allows(&Caller::new().with_steam_id("76561198000000002")));
+        assert!(!guard.allows(&Caller::new()));
+    }
+
+    #[test]
+    fn file_sources() {
+        let guard = Guard::file_sources(["\\x\\My_Mod\\"]);
+        assert!(guard.allows(&Caller::new().with_file_source("x\\my_mod\\addons\\fn_init.sqf")));
+        assert!(!guard.allows(&Caller::new().with_file_source("x\\other\\fn_init.sqf")));
+        assert!(!guard.allows(&Caller::new()));
+    }
+
+    #[test]
+    fn combined() {
+        let guard = Guard::server().or(Guard::steam_ids([PLAYER]));
+        assert!(guard.allows(&Caller::new()));
+        assert!(guard.allows(&Caller::new().with_steam_id(PLAYER)));
+        let guard = Guard::steam_ids([PLAYER]).and(Guard::file_sources(["x\\my_mod"]));
+        assert!(!guard.allows(&Caller::new().with_steam_id(PLAYER)));
+        assert!(guard.allows(
+            &Caller::new()
+                .with_steam_id(PLAYER)
+                .with_file_source("x\\my_mod\\fn_admin.sqf")
+        ));
+    }
+}
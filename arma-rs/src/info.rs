@@ -0,0 +1,230 @@
+//! Describes the commands registered on an extension.
+
+use crate::Value;
+
+/// The built-in command listing every command as an SQF array.
+pub const HELP_COMMAND: &str = "arma_rs:help";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Documentation of a command, registered with [`Group::document`](crate::Group::document).
+///
+/// Used in the schema exported by [`Extension::schema`](crate::Extension::schema).
+pub struct CommandDoc {
+    description: Option<String>,
+    args: Vec<(String, Option<String>)>,
+    errors: Vec<(String, String)>,
+}
+
+impl CommandDoc {
+    #[must_use]
+    /// Creates the documentation of a command with a description.
+    pub fn new<S>(description: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            description: Some(description.into()),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    /// Names and describes the next argument of the command.
+    pub fn arg<S1, S2>(mut self, name: S1, description: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        self.args.push((name.into(), Some(description.into())));
+        self
+    }
+
+    #[must_use]
+    /// Describes an error code the command can return, such as `9`.
+    pub fn error<S1, S2>(mut self, code: S1, description: S2) -> Self
+    where
+        S1: ToString,
+        S2: Into<String>,
+    {
+        self.errors.push((code.to_string(), description.into()));
+        self
+    }
+
+    #[must_use]
+    /// Returns the description of the command.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    #[must_use]
+    /// Returns the name and description of the argument at `index`.
+    pub fn arg_doc(&self, index: usize) -> Option<(&str, Option<&str>)> {
+        self.args
+            .get(index)
+            .map(|(name, description)| (name.as_str(), description.as_deref()))
+    }
+
+    #[must_use]
+    /// Returns the documented error codes and their descriptions.
+    pub fn errors(&self) -> &[(String, String)] {
+        &self.errors
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Describes a registered command.
+pub struct CommandInfo {
+    path: String,
+    args: Vec<String>,
+    path_params: Vec<String>,
+    returns: Option<String>,
+    doc: CommandDoc,
+    aliases: Vec<String>,
+    deprecated: Option<String>,
+    errors: Vec<(String, String)>,
+}
+
+impl CommandInfo {
+    pub(crate) fn new(path: String, args: &[&str], returns: Option<&str>, doc: CommandDoc) -> Self {
+        Self {
+            path,
+            args: args.iter().map(|arg| short_type_name(arg)).collect(),
+            path_params: Vec::new(),
+            returns: returns.map(short_type_name),
+            doc,
+            aliases: Vec::new(),
+            deprecated: None,
+            errors: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_path_params(mut self, types: &[&str]) -> Self {
+        self.path_params = types.iter().map(|ty| short_type_name(ty)).collect();
+        self
+    }
+
+    pub(crate) fn with_aliases(mut self, aliases: Vec<String>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    pub(crate) fn with_deprecated(mut self, deprecated: Option<String>) -> Self {
+        self.deprecated = deprecated;
+        self
+    }
+
+    /// Adds the error codes of the middleware around the command, each code once.
+    pub(crate) fn with_errors(mut self, errors: &[(libc::c_int, String)]) -> Self {
+        for (code, description) in errors {
+            let code = code.to_string();
+            if !self.errors.iter().any(|(c, _)| *c == code) {
+                self.errors.push((code, description.clone()));
+            }
+        }
+        self
+    }
+
+    #[must_use]
+    /// Returns the full path of the command, such as `group:command`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    #[must_use]
+    /// Returns the type names of the arguments, such as `["String", "Vec<i32>"]`.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    #[must_use]
+    /// Returns the type names of the path parameters, such as `["u32"]` for `vehicle:{id}:fuel`.
+    pub fn path_params(&self) -> &[String] {
+        &self.path_params
+    }
+
+    #[must_use]
+    /// Returns the number of arguments.
+    pub fn arity(&self) -> usize {
+        self.args.len()
+    }
+
+    #[must_use]
+    /// Returns the type name of the return value, `None` if nothing is returned.
+    pub fn returns(&self) -> Option<&str> {
+        self.returns.as_deref()
+    }
+
+    #[must_use]
+    /// Returns the documentation registered for the command.
+    pub const fn doc(&self) -> &CommandDoc {
+        &self.doc
+    }
+
+    #[must_use]
+    /// Returns the full paths of the aliases of the command.
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    #[must_use]
+    /// Returns the error codes of the guards, rate limits and middleware around the command.
+    pub fn errors(&self) -> &[(String, String)] {
+        &self.errors
+    }
+
+    #[must_use]
+    /// Returns the deprecation note of the command, `None` if it is not deprecated.
+    pub fn deprecated(&self) -> Option<&str> {
+        self.deprecated.as_deref()
+    }
+
+    /// `[path, [arg, ...], return type, [alias, ...], deprecation note]`,
+    /// the return type and note are `""` when nothing is returned or the command is not deprecated.
+    pub(crate) fn to_value(&self) -> Value {
+        Value::Array(vec![
+            Value::String(self.path.clone()),
+            Value::Array(self.args.iter().cloned().map(Value::String).collect()),
+            Value::String(self.returns.clone().unwrap_or_default()),
+            Value::Array(self.aliases.iter().cloned().map(Value::String).collect()),
+            Value::String(self.deprecated.clone().unwrap_or_default()),
+        ])
+    }
+}
+
+/// Removes the module paths from a type name,
+/// `alloc::vec::Vec<alloc::string::String>` becomes `Vec<String>`.
+pub(crate) fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut segment = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+        } else {
+            short.push_str(segment.rsplit("::").next().unwrap_or_default());
+            segment.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(segment.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_names() {
+        assert_eq!(short_type_name("i32"), "i32");
+        assert_eq!(short_type_name("alloc::string::String"), "String");
+        assert_eq!(
+            short_type_name("std::collections::hash::map::HashMap<alloc::string::String, i32>"),
+            "HashMap<String, i32>"
+        );
+        assert_eq!(short_type_name("(f64, &str)"), "(f64, &str)");
+        assert_eq!(
+            short_type_name("core::option::Option<alloc::vec::Vec<u8>>"),
+            "Option<Vec<u8>>"
+        );
+    }
+}
@@ -2,10 +2,18 @@
 
 //! Library for building powerful Extensions for Arma 3 easily in Rust
 
-use std::sync::Arc;
-
-pub use arma_rs_proc::arma;
-use crossbeam_queue::SegQueue;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Instant,
+};
+
+pub use arma_rs_proc::{arma, command, ArmaEvent};
+#[doc(hidden)]
+pub use inventory;
 pub use libc;
 
 #[cfg(all(target_os = "windows", target_arch = "x86"))]
@@ -14,8 +22,39 @@ pub use link_args;
 #[macro_use]
 extern crate log;
 
+mod caller;
+pub use caller::Caller;
+mod callback;
+pub use callback::{
+    ArmaEvent, Batch, CallbackError, CallbackSender, CallbackStats, Correlation, DeadLetter,
+    DeadLetterReason, OverflowPolicy, Priority, RetryPolicy,
+};
+use callback::{CallbackQueue, DeadLetterHook, Dispatcher, QueueConfig};
 mod ext_result;
 pub use ext_result::IntoExtResult;
+mod logger;
+pub use logger::{Logger, LOG_CALLBACK};
+mod metrics;
+pub use metrics::{CommandMetrics, LATENCY_BUCKETS, STATS_COMMAND};
+mod info;
+pub use info::{CommandDoc, CommandInfo, HELP_COMMAND};
+mod guard;
+pub use guard::{Guard, UNAUTHORIZED};
+mod middleware;
+pub use middleware::{Call, Middleware};
+mod rate_limit;
+mod registry;
+pub use registry::AutoCommand;
+mod route;
+pub use route::{FromPath, Path};
+mod router;
+pub use router::Router;
+mod schema;
+mod sqf;
+pub use rate_limit::{RateLimit, RATE_LIMITED};
+pub use sqf::SqfGenerator;
+mod watchdog;
+pub use watchdog::{FrameBudget, BUDGET_CALLBACK};
 mod value;
 pub use value::{FromArma, IntoArma, Value};
 mod command;
@@ -25,7 +64,7 @@ mod testing;
 
 pub use command::*;
 pub use context::Context;
-pub use group::Group;
+pub use group::{Group, TreeError};
 pub use testing::Result;
 
 #[cfg(windows)]
@@ -46,8 +85,19 @@ pub struct Extension {
     version: String,
     group: Group,
     allow_no_args: bool,
+    legacy_args: Option<char>,
+    separator: char,
     callback: Option<Callback>,
-    callback_queue: Arc<SegQueue<(String, String, Option<Value>)>>,
+    callback_queue: Arc<CallbackQueue>,
+    callback_retry: RetryPolicy,
+    callback_data_limit: usize,
+    dead_letter: Option<Arc<DeadLetterHook>>,
+    call_ids: AtomicU64,
+    // Replaced by `handle_call_context` right before each call, and cloned into its context.
+    caller: Mutex<Caller>,
+    help: bool,
+    metrics: Option<metrics::Metrics>,
+    watchdog: Option<watchdog::Watchdog>,
 }
 
 impl Extension {
@@ -58,6 +108,18 @@ impl Extension {
             version: env!("CARGO_PKG_VERSION").to_string(),
             group: Group::new(),
             allow_no_args: false,
+            legacy_args: None,
+            separator: ':',
+            mounts: Arc::default(),
+            callback_queue: Arc::new(CallbackQueue::default()),
+            callback_config: QueueConfig::default(),
+            callback_retry: RetryPolicy::default(),
+            callback_data_limit: callback::DATA_LIMIT,
+            dead_letter: None,
+            logger: None,
+            help: false,
+            metrics: false,
+            frame_budget: None,
         }
     }
 
@@ -77,17 +139,72 @@ impl Extension {
         self.allow_no_args
     }
 
+    #[must_use]
+    /// Returns the separator between the groups and the command in a path, `:` by default.
+    pub const fn separator(&self) -> char {
+        self.separator
+    }
+
     /// Called by generated code, do not call directly.
     pub fn register_callback(&mut self, callback: Callback) {
         self.callback = Some(callback);
     }
 
+    #[must_use]
+    /// Returns the number of callbacks that have been delivered to Arma or dropped.
+    pub fn callback_stats(&self) -> CallbackStats {
+        self.callback_queue.stats()
+    }
+
+    #[must_use]
+    /// Returns the metrics of every command called so far, by the path the command was added with,
+    /// such as `vehicle:{id}:fuel`. Calls handled by a fallback are recorded as `group:*`.
+    /// Empty unless the extension was built with [`ExtensionBuilder::metrics`].
+    pub fn metrics(&self) -> BTreeMap<String, CommandMetrics> {
+        self.metrics
+            .as_ref()
+            .map(metrics::Metrics::snapshot)
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    /// Returns every command of the extension, sorted by path.
+    pub fn commands(&self) -> Vec<CommandInfo> {
+        let mut commands = Vec::new();
+        self.group.commands("", &[], &mut commands);
+        commands.sort_by(|a, b| a.path().cmp(b.path()));
+        commands
+    }
+
+    #[must_use]
+    /// Returns a JSON document describing every group and command of the extension:
+    /// parameter names and types, return types and the error codes each command can return.
+    /// Names, descriptions and extra error codes come from [`Group::document`].
+    pub fn schema(&self) -> String {
+        schema::to_json(&self.version, self.separator, &self.commands())
+    }
+
+    #[must_use]
+    /// Returns the number of calls over their frame budget, by the path the command was added with.
+    /// Empty unless the extension was built with [`ExtensionBuilder::frame_budget`].
+    pub fn budget_violations(&self) -> BTreeMap<String, u64> {
+        self.watchdog
+            .as_ref()
+            .map(watchdog::Watchdog::violations)
+            .unwrap_or_default()
+    }
+
     #[must_use]
     /// Get a context for interacting with Arma
     pub fn context(&self) -> Context {
         Context::new(self.callback_queue.clone())
     }
 
+    /// Returns the id for a new call.
+    fn next_call_id(&self) -> u64 {
+        self.call_ids.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
     /// Called by generated code, do not call directly.
     /// # Safety
     /// This function is unsafe because it interacts with the C API.
@@ -104,14 +221,187 @@ impl Extension {
         } else {
             return 1;
         };
-        self.group.handle(
-            self.context().with_buffer_size(size),
-            &function,
+        let context = self
+            .context()
+            .with_buffer_size(size)
+            .with_call_id(self.next_call_id())
+            .with_caller(self.caller.lock().unwrap().clone());
+        self.dispatch(context, &function, output, size, args, count)
+    }
+
+    /// Called by generated code, do not call directly.
+    /// Stores the caller information Arma sends before each call.
+    ///
+    /// Arma calls `RVExtensionContext` on the thread of the call, right before it, and runs
+    /// scripts calling extensions on a single thread, so the stored caller belongs to the next call.
+    /// # Safety
+    /// This function is unsafe because it interacts with the C API.
+    pub unsafe fn handle_call_context(&self, args: *mut *mut i8, count: libc::c_int) {
+        let args = middleware::read_args(Some(args), Some(count));
+        *self.caller.lock().unwrap() = Caller::from_args(&args);
+    }
+
+    /// Runs a command, splitting calls without arguments into the command and its arguments
+    /// with the legacy protocol if enabled.
+    unsafe fn dispatch(
+        &self,
+        context: Context,
+        function: &str,
+        output: *mut libc::c_char,
+        size: libc::size_t,
+        args: Option<*mut *mut i8>,
+        count: Option<libc::c_int>,
+    ) -> libc::c_int {
+        if let (None, Some(delimiter)) = (args, self.legacy_args) {
+            let mut parts = function.split(delimiter);
+            let function = parts.next().unwrap_or_default();
+            // The parts come from a C string and can not contain a nul byte.
+            let legacy = parts
+                .filter_map(|arg| std::ffi::CString::new(arg).ok())
+                .collect::<Vec<_>>();
+            let mut pointers = legacy
+                .iter()
+                .map(|arg| arg.as_ptr() as *mut i8)
+                .collect::<Vec<_>>();
+            let count = libc::c_int::try_from(pointers.len()).unwrap_or(libc::c_int::MAX);
+            return self.run(
+                context,
+                function,
+                output,
+                size,
+                Some(pointers.as_mut_ptr()),
+                Some(count),
+            );
+        }
+        self.run(context, function, output, size, args, count)
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    unsafe fn run(
+        &self,
+        context: Context,
+        function: &str,
+        output: *mut libc::c_char,
+        size: libc::size_t,
+        args: Option<*mut *mut i8>,
+        count: Option<libc::c_int>,
+    ) -> libc::c_int {
+        self.execute(context, function, output, size, args, count)
+    }
+
+    #[cfg(feature = "tracing")]
+    /// Runs a command inside a span covering the whole call, including the built-in commands.
+    unsafe fn run(
+        &self,
+        context: Context,
+        function: &str,
+        output: *mut libc::c_char,
+        size: libc::size_t,
+        args: Option<*mut *mut i8>,
+        count: Option<libc::c_int>,
+    ) -> libc::c_int {
+        let span = tracing::info_span!(
+            "command",
+            path = function,
+            args = count.unwrap_or(0),
+            buffer_size = size,
+            code = tracing::field::Empty,
+            duration_us = tracing::field::Empty,
+        );
+        let _enter = span.enter();
+        let start = Instant::now();
+        let code = self.execute(
+            context.with_span(span.clone()),
+            function,
             output,
             size,
             args,
             count,
-        )
+        );
+        span.record("code", code);
+        span.record(
+            "duration_us",
+            u64::try_from(start.elapsed().as_micros()).unwrap_or(u64::MAX),
+        );
+        code
+    }
+
+    /// Runs a command, recording its metrics and checking its frame budget if enabled.
+    unsafe fn execute(
+        &self,
+        context: Context,
+        function: &str,
+        output: *mut libc::c_char,
+        size: libc::size_t,
+        args: Option<*mut *mut i8>,
+        count: Option<libc::c_int>,
+    ) -> libc::c_int {
+        let builtin = (self.help && function == HELP_COMMAND)
+            || (self.metrics.is_some() && function == STATS_COMMAND);
+        // Calls are recorded and limited by the path the command was added with,
+        // so path parameters and fallbacks don't add an entry for every value.
+        // Without metrics or a watchdog, the groups set it on the way to the command.
+        let command = if builtin {
+            Some(Arc::from(function))
+        } else if self.metrics.is_some() || self.watchdog.is_some() {
+            self.group.resolve(function)
+        } else {
+            None
+        };
+        let context = context.with_command(command.clone());
+        if builtin {
+            // Built-in commands are covered by the guards and middleware of the extension.
+            return self.group.wrap(
+                context,
+                function,
+                output,
+                size,
+                args,
+                count,
+                |output, size, _, _| self.builtin(function, output, size),
+            );
+        }
+        if self.metrics.is_none() && self.watchdog.is_none() {
+            return self
+                .group
+                .handle(context, function, output, size, args, count);
+        }
+        if size > 0 {
+            output.write(0);
+        }
+        let start = Instant::now();
+        let code = self
+            .group
+            .handle(context, function, output, size, args, count);
+        let Some(command) = command else {
+            return code;
+        };
+        let elapsed = start.elapsed();
+        if let Some(metrics) = &self.metrics {
+            let output_len = (0..size).take_while(|i| *output.add(*i) != 0).count();
+            metrics.record(&command, code, elapsed, output_len);
+        }
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.check(&command, elapsed, || middleware::read_args(args, count));
+        }
+        code
+    }
+
+    /// Runs the built-in command `function`.
+    unsafe fn builtin(
+        &self,
+        function: &str,
+        output: *mut libc::c_char,
+        size: libc::size_t,
+    ) -> libc::c_int {
+        let value = match (&self.metrics, function) {
+            (Some(metrics), STATS_COMMAND) => metrics.to_value(),
+            _ => Value::Array(self.commands().iter().map(CommandInfo::to_value).collect()),
+        };
+        match write_cstr(value.to_string(), output, size) {
+            Some(_) => 0,
+            None => 4,
+        }
     }
 
     #[must_use]
@@ -122,51 +412,17 @@ impl Extension {
 
     /// Called by generated code, do not call directly.
     pub fn run_callbacks(&self) {
-        let queue = self.callback_queue.clone();
-        let callback = self.callback;
-        std::thread::spawn(move || loop {
-            if let Some((name, func, data)) = queue.pop() {
-                if let Some(c) = callback {
-                    let name = if let Ok(cstring) = std::ffi::CString::new(name) {
-                        cstring
-                    } else {
-                        error!("callback name was not valid");
-                        continue;
-                    };
-                    let func = if let Ok(cstring) = std::ffi::CString::new(func) {
-                        cstring
-                    } else {
-                        error!("callback func was not valid");
-                        continue;
-                    };
-                    let data = if let Ok(cstring) = std::ffi::CString::new(match data {
-                        Some(value) => match value {
-                            Value::String(s) => s,
-                            v => v.to_string(),
-                        },
-                        None => String::new(),
-                    }) {
-                        cstring
-                    } else {
-                        error!("callback data was not valid");
-                        continue;
-                    };
-
-                    let (name, func, data) = (name.into_raw(), func.into_raw(), data.into_raw());
-                    loop {
-                        if c(name, func, data) >= 0 {
-                            break;
-                        }
-                        std::thread::sleep(std::time::Duration::from_millis(1));
-                    }
-                    unsafe {
-                        drop(std::ffi::CString::from_raw(name));
-                        drop(std::ffi::CString::from_raw(func));
-                        drop(std::ffi::CString::from_raw(data));
-                    }
-                }
-            }
-        });
+        let dispatcher = Dispatcher::new(
+            self.callback_queue.clone(),
+            self.callback_retry.clone(),
+            self.dead_letter.clone(),
+            self.callback_data_limit,
+        );
+        if let Some(c) = self.callback {
+            std::thread::spawn(move || {
+                dispatcher.run(|name, func, data| c(name.as_ptr(), func.as_ptr(), data.as_ptr()))
+            });
+        }
     }
 }
 
@@ -175,6 +431,18 @@ pub struct ExtensionBuilder {
     version: String,
     group: Group,
     allow_no_args: bool,
+    legacy_args: Option<char>,
+    separator: char,
+    mounts: Arc<RwLock<router::Mounts>>,
+    callback_queue: Arc<CallbackQueue>,
+    callback_config: QueueConfig,
+    callback_retry: RetryPolicy,
+    callback_data_limit: usize,
+    dead_letter: Option<Arc<DeadLetterHook>>,
+    logger: Option<Logger>,
+    help: bool,
+    metrics: bool,
+    frame_budget: Option<FrameBudget>,
 }
 
 impl ExtensionBuilder {
@@ -186,6 +454,15 @@ impl ExtensionBuilder {
         self
     }
 
+    #[must_use]
+    /// Returns a router mounting and unmounting groups while the extension is running.
+    ///
+    /// The router can be stored in commands to enable features on demand,
+    /// such as a mission calling `modules:enable`.
+    pub fn router(&self) -> Router {
+        Router::new(self.mounts.clone())
+    }
+
     #[inline]
     /// Add a group to the extension.
     pub fn group<S>(mut self, name: S, group: Group) -> Self
@@ -208,27 +485,376 @@ impl ExtensionBuilder {
         self
     }
 
+    #[inline]
+    #[must_use]
+    /// Allows the extension to be called without arguments using the legacy protocol,
+    /// where the command and its arguments are sent as one string split by `delimiter`.
+    /// The arguments are parsed with [`FromArma`] like the arguments of any other call,
+    /// so scripts written for the legacy protocol can use the same commands.
+    /// Example, with `|` as the delimiter:
+    /// ```sqf
+    /// "my_ext" callExtension "my_group:my_func|arg1|42"
+    /// ```
+    /// Calls without a delimiter call the command without arguments.
+    /// The delimiter can not be used in the arguments, and the legacy protocol can not return an error code.
+    pub const fn legacy_args(mut self, delimiter: char) -> Self {
+        self.allow_no_args = true;
+        self.legacy_args = Some(delimiter);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Sets the separator between the groups and the command in a path, `:` by default.
+    ///
+    /// With `/` as the separator the command `sleep` in the group `timer` is called as `timer/sleep`,
+    /// and command names can contain `:`. The built-in commands keep their names.
+    pub const fn separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    #[must_use]
+    /// Returns a sender for the extension's callbacks.
+    ///
+    /// The sender can be stored and used after the extension is built,
+    /// for example from `on_load` code, a logger or another library's callbacks.
+    pub fn callback_sender(&self) -> CallbackSender {
+        CallbackSender::new(self.callback_queue.clone())
+    }
+
+    #[inline]
+    #[must_use]
+    /// Limits the number of callbacks waiting to be sent to Arma.
+    /// By default the queue is unbounded.
+    pub const fn callback_capacity(mut self, capacity: usize) -> Self {
+        self.callback_config.capacity = Some(capacity);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Sets what happens when a callback is sent while the queue is full,
+    /// [`OverflowPolicy::DropNewest`] by default.
+    /// Only used when a capacity is set with [`ExtensionBuilder::callback_capacity`].
+    pub const fn callback_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.callback_config.policy = policy;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Combines callbacks with the same name and function before sending them to Arma.
+    pub fn batch_callbacks<S1, S2>(mut self, name: S1, func: S2, batch: Batch) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        self.callback_config
+            .batches
+            .insert((name.into(), func.into()), batch);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Sets the priority of callbacks with the given name.
+    /// Callbacks sent with [`Context::callback_with_priority`] ignore this setting.
+    pub fn callback_priority<S>(mut self, name: S, priority: Priority) -> Self
+    where
+        S: Into<String>,
+    {
+        self.callback_config
+            .priorities
+            .insert(name.into(), priority);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Adds the id of the originating call to callbacks sent during a command.
+    /// Lets SQF wait for the callback that answers a specific call.
+    pub const fn correlate_callbacks(mut self, correlation: Correlation) -> Self {
+        self.callback_config.correlation = correlation;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Sets how callbacks rejected by Arma are retried.
+    pub fn callback_retry(mut self, policy: RetryPolicy) -> Self {
+        self.callback_retry = policy;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Sets the size in bytes above which callback data is split into parts.
+    /// Defaults to the largest callback data Arma accepts.
+    pub const fn callback_data_limit(mut self, limit: usize) -> Self {
+        self.callback_data_limit = limit;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Sets a handler for callbacks that could not be delivered to Arma.
+    pub fn dead_letter<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(DeadLetter) + Send + Sync + 'static,
+    {
+        self.dead_letter = Some(Arc::new(handler));
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Adds the built-in [`HELP_COMMAND`], returning every command of the extension
+    /// with its argument and return types as an SQF array.
+    /// Guards and middleware added to the extension also run for the help command.
+    pub const fn help_command(mut self) -> Self {
+        self.help = true;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Records call counts, result codes, latency and output size for every command.
+    /// The metrics are available from [`Extension::metrics`] and from SQF through the
+    /// built-in [`STATS_COMMAND`], which is covered by the guards and middleware of the extension.
+    pub const fn metrics(mut self) -> Self {
+        self.metrics = true;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Warns about commands that block Arma for longer than their budget.
+    /// `callExtension` runs on Arma's simulation thread, so slow commands stall the frame.
+    pub fn frame_budget(mut self, budget: FrameBudget) -> Self {
+        self.frame_budget = Some(budget);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Installs a logger for the `log` crate when the extension is built.
+    /// Records from arma-rs, including the callback thread, are otherwise discarded.
+    pub fn logger(mut self, logger: Logger) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Add a middleware that runs around every command of the extension.
+    pub fn middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware,
+    {
+        self.group = self.group.middleware(middleware);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only allows callers allowed by `guard` to run any command of the extension.
+    pub fn guard(mut self, guard: Guard) -> Self {
+        self.group = self.group.guard(guard);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Makes `alias` call the root command `command`, see [`Group::alias`].
+    pub fn alias<S1, S2>(mut self, alias: S1, command: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        self.group = self.group.alias(alias, command);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Marks the root command or alias `name` as deprecated, see [`Group::deprecated`].
+    pub fn deprecated<S1, S2>(mut self, name: S1, note: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        self.group = self.group.deprecated(name, note);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Matches the names of every command and group ignoring ASCII case,
+    /// see [`Group::case_insensitive`].
+    pub fn case_insensitive(mut self) -> Self {
+        self.group = self.group.case_insensitive();
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Handles calls that match no command or group, see [`Group::fallback`].
+    pub fn fallback<F, R>(mut self, handler: F) -> Self
+    where
+        F: Fn(Context, &str, Vec<String>) -> R + Send + Sync + 'static,
+        R: IntoExtResult + 'static,
+    {
+        self.group = self.group.fallback(handler);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only allows callers allowed by `guard` to run the root command `name`.
+    pub fn guard_command<S>(mut self, name: S, guard: Guard) -> Self
+    where
+        S: Into<String>,
+    {
+        self.group = self.group.guard_command(name, guard);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Limits how often the root command `name` can be called.
+    pub fn rate_limit_command<S>(mut self, name: S, limit: RateLimit) -> Self
+    where
+        S: Into<String>,
+    {
+        self.group = self.group.rate_limit_command(name, limit);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Documents the command `name` of the extension.
+    pub fn document<S>(mut self, name: S, doc: CommandDoc) -> Self
+    where
+        S: Into<String>,
+    {
+        self.group = self.group.document(name, doc);
+        self
+    }
+
+    #[must_use]
+    /// Adds every command registered with [`#[command]`](macro@command) to the extension.
+    ///
+    /// Commands already added with the same path are kept, the registered command is skipped
+    /// and reported as [`TreeError::DuplicateCommand`], as are two commands registered at the same path.
+    pub fn auto_commands(mut self) -> Self {
+        for command in registry::commands() {
+            self.group = self.group.auto_command(&command.groups(), command);
+        }
+        self
+    }
+
     #[inline]
     /// Add a command to the extension.
     pub fn command<S, F, I, R>(mut self, name: S, handler: F) -> Self
     where
         S: Into<String>,
-        F: Factory<I, R> + 'static,
+        F: Factory<I, R> + Send + Sync + 'static,
     {
         self.group = self.group.command(name, handler);
         self
     }
 
-    #[inline]
     #[must_use]
-    /// Builds the extension.
-    pub fn finish(self) -> Extension {
+    /// Builds the extension, logging any problem with its commands.
+    /// Use [`try_finish`](Self::try_finish) to fail on problems instead.
+    pub fn finish(mut self) -> Extension {
+        for error in self.prepare() {
+            error!("{}", error);
+        }
+        self.build()
+    }
+
+    /// Builds the extension, checking its commands first.
+    ///
+    /// # Errors
+    /// Returns every problem found in the commands: names added twice to a group,
+    /// empty names, group names containing the separator, commands shadowed by built-in commands
+    /// and the problems of groups mounted with a [`Router`] before building.
+    pub fn try_finish(mut self) -> std::result::Result<Extension, Vec<TreeError>> {
+        let errors = self.prepare();
+        if errors.is_empty() {
+            Ok(self.build())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Configures the groups with the separator and mounts the groups mounted with a [`Router`],
+    /// returning every problem found in the commands.
+    fn prepare(&mut self) -> Vec<TreeError> {
+        self.group.configure("", self.separator, false);
+        let mut errors = self.validate();
+        errors.extend(self.group.set_mounts(self.mounts.clone()));
+        errors
+    }
+
+    fn validate(&self) -> Vec<TreeError> {
+        let mut errors = Vec::new();
+        self.group.validate("", &mut errors);
+        let mut paths = Vec::new();
+        self.group.commands("", &[], &mut paths);
+        let mut builtin = Vec::new();
+        if self.help {
+            builtin.push(HELP_COMMAND);
+        }
+        if self.metrics {
+            builtin.push(STATS_COMMAND);
+        }
+        let mut shadowed = paths
+            .iter()
+            .filter(|command| builtin.contains(&command.path()))
+            .map(|command| TreeError::Shadowed(command.path().to_string()))
+            .collect::<Vec<_>>();
+        shadowed.sort_by_key(ToString::to_string);
+        errors.extend(shadowed);
+        errors
+    }
+
+    /// Builds the extension, the group must be prepared first.
+    fn build(mut self) -> Extension {
+        if let Some(logger) = self.logger.take() {
+            if let Err(e) = logger.install(self.callback_sender()) {
+                warn!("failed to install logger: {}", e);
+            }
+        }
+        let watchdog = self
+            .frame_budget
+            .take()
+            .map(|budget| watchdog::Watchdog::new(budget, self.callback_sender()));
         Extension {
             version: self.version,
             group: self.group,
             allow_no_args: self.allow_no_args,
+            legacy_args: self.legacy_args,
+            separator: self.separator,
             callback: None,
-            callback_queue: Arc::new(SegQueue::new()),
+            callback_queue: {
+                self.callback_queue.configure(self.callback_config);
+                self.callback_queue
+            },
+            callback_retry: self.callback_retry,
+            callback_data_limit: self.callback_data_limit,
+            dead_letter: self.dead_letter,
+            call_ids: AtomicU64::new(0),
+            caller: Mutex::new(Caller::default()),
+            help: self.help,
+            metrics: if self.metrics {
+                Some(metrics::Metrics::default())
+            } else {
+                None
+            },
+            watchdog,
         }
     }
 }
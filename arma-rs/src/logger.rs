@@ -0,0 +1,392 @@
+//! A `log` implementation writing RPT style files and forwarding records to SQF.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::CallbackSender;
+
+/// The callback name used to forward log records to SQF.
+/// The function is the level of the record and the data is `[target, message]`.
+pub const LOG_CALLBACK: &str = "arma_rs:log";
+
+/// Records from the callback dispatcher are never forwarded,
+/// as a failing dispatcher would otherwise log about its own log callbacks.
+const CALLBACK_TARGET: &str = "arma_rs::callback";
+
+#[derive(Debug, Clone)]
+/// Configures the logger installed by
+/// [`ExtensionBuilder::logger`](crate::ExtensionBuilder::logger).
+pub struct Logger {
+    level: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+    file: bool,
+    path: Option<PathBuf>,
+    max_size: u64,
+    max_files: usize,
+    forward: LevelFilter,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            modules: Vec::new(),
+            file: false,
+            path: None,
+            max_size: 10 * 1024 * 1024,
+            max_files: 3,
+            forward: LevelFilter::Off,
+        }
+    }
+}
+
+impl Logger {
+    #[must_use]
+    /// Creates a logger for records at `level` or above, with no outputs enabled.
+    pub fn new(level: LevelFilter) -> Self {
+        Self {
+            level,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    /// Sets the level for records from `module` and its submodules,
+    /// overriding the default level.
+    pub fn module<S>(mut self, module: S, level: LevelFilter) -> Self
+    where
+        S: Into<String>,
+    {
+        self.modules.push((module.into(), level));
+        self
+    }
+
+    #[must_use]
+    /// Writes records to a file named after the extension, next to the extension binary.
+    /// `my_ext_x64.dll` logs to `my_ext_x64.rpt`.
+    pub const fn file(mut self) -> Self {
+        self.file = true;
+        self
+    }
+
+    #[must_use]
+    /// Writes records to `path`.
+    /// Relative paths are resolved from the directory of the extension binary.
+    pub fn path<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.file = true;
+        self.path = Some(path.into());
+        self
+    }
+
+    #[must_use]
+    /// Starts a new file once the current one reaches `max_size` bytes,
+    /// keeping up to `max_files` old files as `<name>.1`, `<name>.2`, ...
+    pub const fn rotate(mut self, max_size: u64, max_files: usize) -> Self {
+        self.max_size = max_size;
+        self.max_files = max_files;
+        self
+    }
+
+    #[must_use]
+    /// Forwards records at `level` or above to SQF as callbacks named [`LOG_CALLBACK`].
+    pub const fn forward(mut self, level: LevelFilter) -> Self {
+        self.forward = level;
+        self
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target == module
+                    || target
+                        .strip_prefix(module.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.level, Ord::max)
+    }
+
+    fn resolve_path(&self) -> Option<PathBuf> {
+        let binary = extension_path();
+        match &self.path {
+            Some(path) if path.is_absolute() => Some(path.clone()),
+            Some(path) => Some(
+                binary
+                    .as_deref()
+                    .and_then(Path::parent)
+                    .map_or_else(|| path.clone(), |dir| dir.join(path)),
+            ),
+            None => binary.map(|binary| binary.with_extension("rpt")),
+        }
+    }
+
+    /// Installs the logger, failing if another logger is already installed.
+    pub(crate) fn install(self, sender: CallbackSender) -> Result<(), log::SetLoggerError> {
+        let file = if self.file {
+            self.resolve_path()
+                .map(|path| Mutex::new(RotatingFile::new(path, self.max_size, self.max_files)))
+        } else {
+            None
+        };
+        let max_level = self.max_level();
+        log::set_boxed_logger(Box::new(Bridge {
+            config: self,
+            file,
+            sender,
+        }))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+struct Bridge {
+    config: Logger,
+    file: Option<Mutex<RotatingFile>>,
+    sender: CallbackSender,
+}
+
+impl Log for Bridge {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.config.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Some(file) = &self.file {
+            let line = format_line(SystemTime::now(), record);
+            if let Err(e) = file.lock().unwrap().write_line(&line) {
+                eprintln!("arma-rs: failed to write log file: {}", e);
+            }
+        }
+        if record.level() <= self.config.forward && !record.target().starts_with(CALLBACK_TARGET) {
+            let _ = self.sender.callback(
+                LOG_CALLBACK,
+                record.level().as_str(),
+                Some(vec![record.target().to_string(), record.args().to_string()]),
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            file.lock().unwrap().flush();
+        }
+    }
+}
+
+/// Formats a record like a line of an Arma RPT file, the time is in UTC.
+fn format_line(time: SystemTime, record: &Record) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) % 86400;
+    format!(
+        "{:2}:{:02}:{:02} [{}] {}: {}\n",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    const fn new(path: PathBuf, max_size: u64, max_files: usize) -> Self {
+        Self {
+            path,
+            max_size,
+            max_files,
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            self.size = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        if let Some(file) = &mut self.file {
+            file.write_all(line.as_bytes())?;
+            self.size += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file = None;
+        if self.max_files > 0 {
+            let oldest = self.rotated(self.max_files);
+            if oldest.exists() {
+                std::fs::remove_file(oldest)?;
+            }
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated(index);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = Some(File::create(&self.path)?);
+        self.size = 0;
+        Ok(())
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn flush(&mut self) {
+        if let Some(file) = &mut self.file {
+            let _ = file.flush();
+        }
+    }
+}
+
+#[cfg(unix)]
+/// Returns the path of the binary containing arma-rs.
+fn extension_path() -> Option<PathBuf> {
+    use std::{ffi::CStr, os::unix::ffi::OsStrExt};
+
+    let mut info = std::mem::MaybeUninit::<libc::Dl_info>::uninit();
+    let found = unsafe { libc::dladdr(extension_path as *const libc::c_void, info.as_mut_ptr()) };
+    if found == 0 {
+        return None;
+    }
+    let info = unsafe { info.assume_init() };
+    if info.dli_fname.is_null() {
+        return None;
+    }
+    let name = unsafe { CStr::from_ptr(info.dli_fname) };
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(name.to_bytes())))
+}
+
+#[cfg(windows)]
+/// Returns the path of the binary containing arma-rs.
+fn extension_path() -> Option<PathBuf> {
+    use std::os::windows::ffi::OsStringExt;
+
+    const GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS: u32 = 0x4;
+    const GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT: u32 = 0x2;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleExW(flags: u32, name: *const u16, module: *mut *mut libc::c_void) -> i32;
+        fn GetModuleFileNameW(module: *mut libc::c_void, name: *mut u16, size: u32) -> u32;
+    }
+
+    let mut module = std::ptr::null_mut();
+    let found = unsafe {
+        GetModuleHandleExW(
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            extension_path as *const u16,
+            &mut module,
+        )
+    };
+    if found == 0 {
+        return None;
+    }
+    let mut name = vec![0u16; 1024];
+    let len = unsafe { GetModuleFileNameW(module, name.as_mut_ptr(), name.len() as u32) } as usize;
+    if len == 0 || len >= name.len() {
+        return None;
+    }
+    Some(PathBuf::from(std::ffi::OsString::from_wide(&name[..len])))
+}
+
+#[cfg(test)]
+mod tests {
+    use log::Level;
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("arma-rs-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("test.rpt")
+    }
+
+    #[test]
+    fn module_levels() {
+        let logger = Logger::new(LevelFilter::Warn)
+            .module("my_ext", LevelFilter::Info)
+            .module("my_ext::noisy", LevelFilter::Error);
+        assert_eq!(logger.level_for("other"), LevelFilter::Warn);
+        assert_eq!(logger.level_for("my_ext"), LevelFilter::Info);
+        assert_eq!(logger.level_for("my_ext::timer"), LevelFilter::Info);
+        assert_eq!(logger.level_for("my_ext_other"), LevelFilter::Warn);
+        assert_eq!(logger.level_for("my_ext::noisy::inner"), LevelFilter::Error);
+        assert_eq!(logger.max_level(), LevelFilter::Info);
+    }
+
+    #[test]
+    fn line() {
+        let record = Record::builder()
+            .level(Level::Warn)
+            .target("my_ext")
+            .args(format_args!("hello"))
+            .build();
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(86400 + 3 * 3600 + 4 * 60 + 5);
+        assert_eq!(
+            format_line(time, &record),
+            " 3:04:05 [WARN] my_ext: hello\n"
+        );
+    }
+
+    #[test]
+    fn extension_path_found() {
+        assert!(extension_path().unwrap().exists());
+    }
+
+    #[test]
+    fn rotate() {
+        let path = temp_path("rotate");
+        let mut file = RotatingFile::new(path.clone(), 10, 2);
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_line(line).unwrap();
+        }
+        let read = |path: &Path| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(&path), "fourth\n");
+        assert_eq!(read(&file.rotated(1)), "third\n");
+        assert_eq!(read(&file.rotated(2)), "second\n");
+        assert!(!file.rotated(3).exists());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+//! Per-command call metrics.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::Value;
+
+/// The built-in command returning the metrics as an SQF array.
+pub const STATS_COMMAND: &str = "arma_rs:stats";
+
+/// The upper bounds of the latency histogram buckets.
+/// The last bucket of [`CommandMetrics::latency`] counts calls slower than all of them.
+pub const LATENCY_BUCKETS: [Duration; 5] = [
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Metrics recorded for a single command path.
+pub struct CommandMetrics {
+    /// The number of times the command was called.
+    pub calls: u64,
+    /// The number of calls that returned each code.
+    pub codes: BTreeMap<libc::c_int, u64>,
+    /// The number of calls in each bucket of [`LATENCY_BUCKETS`].
+    pub latency: [u64; LATENCY_BUCKETS.len() + 1],
+    /// The time spent in all calls.
+    pub total_time: Duration,
+    /// The number of bytes written to the output by all calls.
+    pub output_bytes: u64,
+}
+
+impl CommandMetrics {
+    #[must_use]
+    /// Returns the mean time spent in a call.
+    pub fn mean_latency(&self) -> Duration {
+        u32::try_from(self.calls)
+            .ok()
+            .and_then(|calls| self.total_time.checked_div(calls))
+            .unwrap_or_default()
+    }
+
+    fn record(&mut self, code: libc::c_int, elapsed: Duration, output_len: usize) {
+        self.calls += 1;
+        *self.codes.entry(code).or_default() += 1;
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| elapsed <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.latency[bucket] += 1;
+        self.total_time += elapsed;
+        self.output_bytes += output_len as u64;
+    }
+
+    /// `[path, calls, [[code, count], ...], [bucket, ...], total ms, output bytes]`
+    fn to_value(&self, path: &str) -> Value {
+        #[allow(clippy::cast_precision_loss)]
+        let number = |n: u64| Value::Number(n as f64);
+        Value::Array(vec![
+            Value::String(path.to_string()),
+            number(self.calls),
+            Value::Array(
+                self.codes
+                    .iter()
+                    .map(|(code, count)| {
+                        Value::Array(vec![Value::Number(f64::from(*code)), number(*count)])
+                    })
+                    .collect(),
+            ),
+            Value::Array(self.latency.iter().map(|count| number(*count)).collect()),
+            Value::Number(self.total_time.as_secs_f64() * 1000.0),
+            number(self.output_bytes),
+        ])
+    }
+}
+
+#[derive(Default)]
+/// Metrics of every command that has been called.
+pub(crate) struct Metrics {
+    commands: Mutex<HashMap<String, CommandMetrics>>,
+}
+
+impl Metrics {
+    pub(crate) fn record(
+        &self,
+        path: &str,
+        code: libc::c_int,
+        elapsed: Duration,
+        output_len: usize,
+    ) {
+        let mut commands = self.commands.lock().unwrap();
+        if let Some(metrics) = commands.get_mut(path) {
+            metrics.record(code, elapsed, output_len);
+        } else {
+            let mut metrics = CommandMetrics::default();
+            metrics.record(code, elapsed, output_len);
+            commands.insert(path.to_string(), metrics);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> BTreeMap<String, CommandMetrics> {
+        self.commands
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, metrics)| (path.clone(), metrics.clone()))
+            .collect()
+    }
+
+    /// Returns the metrics of every command as an SQF array, sorted by path.
+    pub(crate) fn to_value(&self) -> Value {
+        Value::Array(
+            self.snapshot()
+                .iter()
+                .map(|(path, metrics)| metrics.to_value(path))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let metrics = Metrics::default();
+        metrics.record("a", 0, Duration::from_micros(50), 3);
+        metrics.record("a", 9, Duration::from_millis(5), 4);
+        metrics.record("a", 0, Duration::from_secs(2), 0);
+        let snapshot = metrics.snapshot();
+        let a = &snapshot["a"];
+        assert_eq!(a.calls, 3);
+        assert_eq!(a.codes, BTreeMap::from([(0, 2), (9, 1)]));
+        assert_eq!(a.latency, [1, 0, 1, 0, 0, 1]);
+        assert_eq!(a.output_bytes, 7);
+        assert_eq!(
+            a.mean_latency(),
+            (Duration::from_micros(50) + Duration::from_millis(5) + Duration::from_secs(2)) / 3
+        );
+    }
+
+    #[test]
+    fn value() {
+        let metrics = Metrics::default();
+        metrics.record("b", 0, Duration::from_millis(2), 5);
+        metrics.record("a", 21, Duration::ZERO, 0);
+        assert_eq!(
+            metrics.to_value().to_string(),
+            r#"[["a",1,[[21,1]],[1,0,0,0,0,0],0,0],["b",1,[[0,1]],[0,0,1,0,0,0],2,5]]"#
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Hooks that run around every command of a group.
+
+use std::ffi::{CStr, CString};
+
+use crate::Context;
+
+/// A command call seen by a [`Middleware`].
+pub struct Call {
+    path: String,
+    args: Vec<String>,
+    context: Context,
+}
+
+impl Call {
+    pub(crate) fn new(path: &str, args: Vec<String>, context: Context) -> Self {
+        Self {
+            path: path.to_string(),
+            args,
+            context,
+        }
+    }
+
+    #[must_use]
+    /// Returns the full path of the command, such as `group:command`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    #[must_use]
+    /// Returns the path the command was added with, such as `vehicle:{id}:fuel`.
+    /// Returns `None` if no command matches the call, it returns code 1 unless middleware rejects it.
+    pub fn command(&self) -> Option<&str> {
+        self.context.command()
+    }
+
+    #[must_use]
+    /// Returns the arguments as sent by Arma, strings are still quoted.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Returns the arguments for modification, the command receives the modified arguments.
+    pub fn args_mut(&mut self) -> &mut Vec<String> {
+        &mut self.args
+    }
+
+    #[must_use]
+    /// Returns the context of the call.
+    pub const fn context(&self) -> &Context {
+        &self.context
+    }
+}
+
+/// Runs code before and after each command of a group and its subgroups.
+///
+/// Registered with [`Group::middleware`](crate::Group::middleware) or
+/// [`ExtensionBuilder::middleware`](crate::ExtensionBuilder::middleware).
+/// Middleware runs in the order it was added before the command, and in reverse order after it.
+pub trait Middleware: Send + Sync + 'static {
+    /// Called before the command.
+    ///
+    /// # Errors
+    /// Returning an error code skips the command and the middleware added after this one,
+    /// and returns the code to Arma. Middleware that already ran `before` still runs `after`.
+    fn before(&self, _call: &mut Call) -> Result<(), libc::c_int> {
+        Ok(())
+    }
+
+    /// Called after the command with its return code and output.
+    fn after(&self, _call: &Call, _code: libc::c_int, _output: &str) {}
+
+    /// Returns the error codes `before` can return and their descriptions,
+    /// listed for the commands it runs around by [`Extension::schema`](crate::Extension::schema).
+    fn errors(&self) -> Vec<(libc::c_int, String)> {
+        Vec::new()
+    }
+}
+
+/// Runs `command` wrapped by `middleware`.
+/// `args` and `count` are replaced by the arguments of `call` when the command is run.
+///
+/// # Safety
+/// `args` must point to `count` valid C strings and `output` to a buffer of `size` bytes.
+pub(crate) unsafe fn run<C>(
+    middleware: &[Box<dyn Middleware>],
+    mut call: Call,
+    output: *mut libc::c_char,
+    size: libc::size_t,
+    args: Option<*mut *mut i8>,
+    count: Option<libc::c_int>,
+    command: C,
+) -> libc::c_int
+where
+    C: FnOnce(
+        *mut libc::c_char,
+        libc::size_t,
+        Option<*mut *mut i8>,
+        Option<libc::c_int>,
+    ) -> libc::c_int,
+{
+    for (index, current) in middleware.iter().enumerate() {
+        if let Err(code) = current.before(&mut call) {
+            for middleware in middleware[..index].iter().rev() {
+                middleware.after(&call, code, "");
+            }
+            return code;
+        }
+    }
+    let strings = call
+        .args
+        .iter()
+        .map(|arg| CString::new(arg.as_str()).unwrap_or_default())
+        .collect::<Vec<_>>();
+    let mut pointers = strings
+        .iter()
+        .map(|arg| arg.as_ptr() as *mut i8)
+        .collect::<Vec<_>>();
+    let (args, count) = if args.is_none() && pointers.is_empty() {
+        (None, count)
+    } else {
+        (
+            Some(pointers.as_mut_ptr()),
+            Some(libc::c_int::try_from(pointers.len()).unwrap_or(libc::c_int::MAX)),
+        )
+    };
+    if size > 0 {
+        output.write(0);
+    }
+    let code = command(output, size, args, count);
+    let result = if size > 0 {
+        CStr::from_ptr(output).to_string_lossy().into_owned()
+    } else {
+        String::new()
+    };
+    for middleware in middleware.iter().rev() {
+        middleware.after(&call, code, &result);
+    }
+    code
+}
+
+/// Copies the arguments sent by Arma.
+///
+/// # Safety
+/// `args` must point to `count` valid C strings.
+pub(crate) unsafe fn read_args(
+    args: Option<*mut *mut i8>,
+    count: Option<libc::c_int>,
+) -> Vec<String> {
+    let count = usize::try_from(count.unwrap_or(0)).unwrap_or(0);
+    args.map_or_else(Vec::new, |args| {
+        (0..count)
+            .map(|i| CStr::from_ptr(*args.add(i)).to_string_lossy().into_owned())
+            .collect()
+    })
+}
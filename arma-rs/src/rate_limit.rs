@@ -0,0 +1,192 @@
+//! Rejects calls beyond a rate, as middleware.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{Call, Context, Middleware};
+
+/// The code returned for calls rejected by a [`RateLimit`].
+pub const RATE_LIMITED: libc::c_int = 5;
+
+/// The caller Steam ID and command path a bucket is kept for, when limited separately.
+type Key = (Option<String>, Option<String>);
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// The buckets of the callers and commands that called recently.
+struct Buckets {
+    buckets: HashMap<Key, Bucket>,
+    pruned: Instant,
+}
+
+/// Limits how often commands can be called, using a token bucket.
+///
+/// A rate limit is [`Middleware`], added to a group it limits the commands of the group,
+/// added to the extension it limits every command.
+/// A single command is limited with [`Group::rate_limit_command`](crate::Group::rate_limit_command).
+/// Calls over the limit return [`RATE_LIMITED`] without running the command,
+/// calls matching no command are not counted.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use arma_rs::{Extension, Group, RateLimit};
+/// Extension::build()
+///     .group(
+///         "db",
+///         Group::new().middleware(RateLimit::new(10, Duration::from_secs(1)).per_caller()),
+///     )
+///     .finish();
+/// ```
+pub struct RateLimit {
+    capacity: u32,
+    period: Duration,
+    per_caller: bool,
+    per_command: bool,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimit {
+    #[must_use]
+    /// Allows bursts of up to `capacity` calls, refilled evenly over `period`.
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        Self {
+            capacity,
+            period,
+            per_caller: false,
+            per_command: false,
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                pruned: Instant::now(),
+            }),
+        }
+    }
+
+    #[must_use]
+    /// Gives every caller Steam ID its own limit.
+    /// Calls without a Steam ID, such as from the server, share a limit.
+    pub const fn per_caller(mut self) -> Self {
+        self.per_caller = true;
+        self
+    }
+
+    #[must_use]
+    /// Gives every command its own limit, instead of sharing one across the group.
+    /// Commands are told apart by the path they were added with, calls to `vehicle:1:fuel`
+    /// and `vehicle:2:fuel` share the limit of `vehicle:{id}:fuel`.
+    pub const fn per_command(mut self) -> Self {
+        self.per_command = true;
+        self
+    }
+
+    /// Takes a token from the bucket of `key`, returns false if it is empty.
+    /// Buckets unused for a whole period are full again and are dropped once every period,
+    /// so callers that left don't keep their bucket.
+    fn take(&self, key: Key, now: Instant) -> bool {
+        let capacity = f64::from(self.capacity);
+        let mut buckets = self.buckets.lock().unwrap();
+        if now.saturating_duration_since(buckets.pruned) >= self.period {
+            let period = self.period;
+            buckets
+                .buckets
+                .retain(|_, bucket| now.saturating_duration_since(bucket.updated) < period);
+            buckets.pruned = now;
+        }
+        let bucket = buckets.buckets.entry(key).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let refill = if self.period.is_zero() {
+            capacity
+        } else {
+            now.saturating_duration_since(bucket.updated).as_secs_f64() / self.period.as_secs_f64()
+                * capacity
+        };
+        bucket.tokens = (bucket.tokens + refill).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes a token for the call, returns [`RATE_LIMITED`] if the limit is reached.
+    pub(crate) fn check(&self, context: &Context) -> Result<(), libc::c_int> {
+        let caller = if self.per_caller {
+            context.caller().steam_id().map(ToString::to_string)
+        } else {
+            None
+        };
+        let command = if self.per_command {
+            context.command().map(ToString::to_string)
+        } else {
+            None
+        };
+        if self.take((caller, command), Instant::now()) {
+            Ok(())
+        } else {
+            Err(RATE_LIMITED)
+        }
+    }
+}
+
+impl Middleware for RateLimit {
+    fn before(&self, call: &mut Call) -> Result<(), libc::c_int> {
+        if call.command().is_none() {
+            return Ok(());
+        }
+        self.check(call.context())
+    }
+
+    fn errors(&self) -> Vec<(libc::c_int, String)> {
+        vec![(RATE_LIMITED, "Rate limited".to_string())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refill() {
+        let limit = RateLimit::new(2, Duration::from_secs(1));
+        let start = Instant::now();
+        assert!(limit.take((None, None), start));
+        assert!(limit.take((None, None), start));
+        assert!(!limit.take((None, None), start));
+        assert!(!limit.take((None, None), start + Duration::from_millis(400)));
+        assert!(limit.take((None, None), start + Duration::from_millis(600)));
+        assert!(limit.take((None, None), start + Duration::from_secs(5)));
+        assert!(limit.take((None, None), start + Duration::from_secs(5)));
+        assert!(!limit.take((None, None), start + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn keys() {
+        let limit = RateLimit::new(1, Duration::from_secs(60));
+        let now = Instant::now();
+        assert!(limit.take((Some("a".to_string()), None), now));
+        assert!(!limit.take((Some("a".to_string()), None), now));
+        assert!(limit.take((Some("b".to_string()), None), now));
+        assert!(limit.take((None, None), now));
+    }
+
+    #[test]
+    fn prune() {
+        let limit = RateLimit::new(1, Duration::from_secs(60));
+        let now = Instant::now();
+        assert!(limit.take((Some("a".to_string()), None), now));
+        assert!(limit.take((Some("b".to_string()), None), now + Duration::from_secs(30)));
+        assert_eq!(limit.buckets.lock().unwrap().buckets.len(), 2);
+        assert!(limit.take((None, None), now + Duration::from_secs(61)));
+        assert_eq!(limit.buckets.lock().unwrap().buckets.len(), 2);
+        assert!(!limit.take((Some("b".to_string()), None), now + Duration::from_secs(61)));
+    }
+}
@@ -0,0 +1,55 @@
+//! Commands registered with `#[arma_rs::command]`.
+
+use crate::Group;
+
+#[doc(hidden)]
+/// A command registered with `#[arma_rs::command]`, collected by [`inventory`].
+pub struct AutoCommand {
+    group: &'static str,
+    name: &'static str,
+    register: fn(Group) -> Group,
+}
+
+impl AutoCommand {
+    #[must_use]
+    /// Used by generated code.
+    pub const fn new(
+        group: &'static str,
+        name: &'static str,
+        register: fn(Group) -> Group,
+    ) -> Self {
+        Self {
+            group,
+            name,
+            register,
+        }
+    }
+
+    /// Returns the groups the command is in, empty for commands at the root of the extension.
+    pub(crate) fn groups(&self) -> Vec<&'static str> {
+        if self.group.is_empty() {
+            Vec::new()
+        } else {
+            self.group.split(':').collect()
+        }
+    }
+
+    pub(crate) const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub(crate) fn register(&self, group: Group) -> Group {
+        (self.register)(group)
+    }
+}
+
+inventory::collect!(AutoCommand);
+
+/// Returns every registered command, sorted by path.
+pub(crate) fn commands() -> Vec<&'static AutoCommand> {
+    let mut commands = inventory::iter::<AutoCommand>
+        .into_iter()
+        .collect::<Vec<_>>();
+    commands.sort_by_key(|command| (command.group, command.name));
+    commands
+}
@@ -0,0 +1,256 @@
+//! Commands with path parameters, such as `vehicle:{id}:fuel`.
+
+use crate::FromArma;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The values captured by the parameters of a command path.
+///
+/// Taken by a command after its optional [`Context`](crate::Context), before its arguments.
+/// A single parameter is converted to `T`, several parameters are converted to a tuple,
+/// each parameter with its own [`FromArma`]. See [`FromPath`] for the types that can be taken.
+/// A parameter that can not be converted returns the code `7x`, x being its position.
+///
+/// ```
+/// # use arma_rs::{Extension, Path};
+/// Extension::build()
+///     .command("vehicle:{id}:fuel", |Path(id): Path<u32>| -> u32 { id * 10 })
+///     .command("{table}:{id}:get", |Path((table, id)): Path<(String, u32)>| {})
+///     .finish();
+/// ```
+pub struct Path<T>(pub T);
+
+impl<T> Path<T> {
+    #[must_use]
+    /// Returns the converted value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Path<T>
+where
+    T: FromPath,
+{
+    /// Converts the captured segments, never implemented as [`FromArma`]
+    /// so that commands can tell a `Path` apart from their arguments.
+    pub(crate) fn from_segments(segments: &[String]) -> Result<Self, (usize, String)> {
+        T::from_path(segments).map(Self)
+    }
+}
+
+/// A trait for converting the parameters of a command path to a Rust value.
+///
+/// Implemented for the types implementing [`FromArma`] by arma-rs, taking a single parameter,
+/// and for tuples of [`FromArma`] types, taking a parameter per element.
+/// Other types can be taken in a tuple with a single element, `Path<(T,)>`.
+pub trait FromPath: Sized {
+    /// Converts the values of the parameters to a Rust value.
+    /// # Errors
+    /// Will return the position of the parameter that cannot be converted, and the error.
+    fn from_path(segments: &[String]) -> Result<Self, (usize, String)>;
+
+    /// Returns the type names of the parameters.
+    fn type_names() -> Vec<&'static str> {
+        vec![std::any::type_name::<Self>()]
+    }
+}
+
+fn count_error(expected: usize, segments: &[String]) -> (usize, String) {
+    (
+        segments.len().min(expected),
+        format!(
+            "expected {} path parameters, found {}",
+            expected,
+            segments.len()
+        ),
+    )
+}
+
+macro_rules! impl_from_path {
+    ($($t:ty),*) => {
+        $(
+            impl FromPath for $t {
+                fn from_path(segments: &[String]) -> Result<Self, (usize, String)> {
+                    match segments {
+                        [segment] => Self::from_arma(segment.clone()).map_err(|e| (0, e)),
+                        segments => Err(count_error(1, segments)),
+                    }
+                }
+            }
+        )*
+    };
+}
+impl_from_path!(String, i8, i16, i32, i64, u8, u16, u32, u64, f32, f64, bool, char);
+
+macro_rules! impl_from_path_tuple {
+    ($count:expr, $($t:ident $i:tt),*) => {
+        impl<$($t),*> FromPath for ($($t,)*)
+        where
+            $($t: FromArma),*
+        {
+            fn from_path(segments: &[String]) -> Result<Self, (usize, String)> {
+                if segments.len() != $count {
+                    return Err(count_error($count, segments));
+                }
+                Ok((
+                    $($t::from_arma(segments[$i].clone()).map_err(|e| ($i, e))?,)*
+                ))
+            }
+
+            fn type_names() -> Vec<&'static str> {
+                vec![$(std::any::type_name::<$t>()),*]
+            }
+        }
+    };
+}
+impl_from_path_tuple!(1, A 0);
+impl_from_path_tuple!(2, A 0, B 1);
+impl_from_path_tuple!(3, A 0, B 1, C 2);
+impl_from_path_tuple!(4, A 0, B 1, C 2, D 3);
+impl_from_path_tuple!(5, A 0, B 1, C 2, D 3, E 4);
+impl_from_path_tuple!(6, A 0, B 1, C 2, D 3, E 4, F 5);
+impl_from_path_tuple!(7, A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_from_path_tuple!(8, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_from_path_tuple!(9, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_from_path_tuple!(10, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Parameter,
+}
+
+/// A command name containing `:` or parameters, matched segment by segment.
+pub(crate) struct Route {
+    name: String,
+    segments: Vec<Segment>,
+}
+
+impl Route {
+    /// Returns if `name` must be matched as a route instead of by name.
+    pub(crate) fn is_route(name: &str, separator: char) -> bool {
+        name.contains(separator) || name.contains('{')
+    }
+
+    /// Returns `name` with its parameters unnamed, and in lowercase if `ignore_case` is set.
+    /// Two names with the same key match the same calls.
+    pub(crate) fn key(name: &str, separator: char, ignore_case: bool) -> String {
+        name.split(separator)
+            .map(|segment| {
+                if segment.starts_with('{') && segment.ends_with('}') {
+                    "{}".to_string()
+                } else if ignore_case {
+                    segment.to_ascii_lowercase()
+                } else {
+                    segment.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(&separator.to_string())
+    }
+
+    pub(crate) fn new(name: String, separator: char) -> Self {
+        let segments = name
+            .split(separator)
+            .map(|segment| {
+                if segment.starts_with('{') && segment.ends_with('}') {
+                    Segment::Parameter
+                } else {
+                    Segment::Literal(segment.to_string())
+                }
+            })
+            .collect();
+        Self { name, segments }
+    }
+
+    /// Returns the name the command was added with.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the values of the parameters if `function` matches the route.
+    pub(crate) fn matches(
+        &self,
+        function: &str,
+        separator: char,
+        ignore_case: bool,
+    ) -> Option<Vec<String>> {
+        let mut parts = function.split(separator);
+        let mut captures = Vec::new();
+        for segment in &self.segments {
+            let part = parts.next()?;
+            match segment {
+                Segment::Literal(literal)
+                    if literal == part || (ignore_case && literal.eq_ignore_ascii_case(part)) => {}
+                Segment::Literal(_) => return None,
+                Segment::Parameter if part.is_empty() => return None,
+                Segment::Parameter => captures.push(part.to_string()),
+            }
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(captures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches() {
+        let route = Route::new("vehicle:{id}:fuel".to_string(), ':');
+        assert_eq!(
+            route.matches("vehicle:42:fuel", ':', false),
+            Some(vec!["42".to_string()])
+        );
+        assert_eq!(route.matches("vehicle:42", ':', false), None);
+        assert_eq!(route.matches("vehicle:42:fuel:more", ':', false), None);
+        assert_eq!(route.matches("vehicle::fuel", ':', false), None);
+        assert_eq!(route.matches("truck:42:fuel", ':', false), None);
+        let route = Route::new("a:b".to_string(), ':');
+        assert_eq!(route.matches("a:b", ':', false), Some(vec![]));
+        assert_eq!(route.matches("A:b", ':', false), None);
+        assert_eq!(route.matches("A:b", ':', true), Some(vec![]));
+        let route = Route::new("vehicle/{id}".to_string(), '/');
+        assert_eq!(
+            route.matches("vehicle/a:b", '/', false),
+            Some(vec!["a:b".to_string()])
+        );
+    }
+
+    #[test]
+    fn key() {
+        assert_eq!(Route::key("a:{x}:B", ':', false), "a:{}:B");
+        assert_eq!(Route::key("a:{y}:B", ':', true), "a:{}:b");
+        assert_eq!(Route::key("Hello", '/', true), "hello");
+    }
+
+    #[test]
+    fn path() {
+        assert_eq!(
+            Path::<u32>::from_segments(&["42".to_string()]),
+            Ok(Path(42))
+        );
+        assert_eq!(
+            Path::<(String, u8)>::from_segments(&["players".to_string(), "7".to_string()]),
+            Ok(Path(("players".to_string(), 7)))
+        );
+        assert!(Path::<u32>::from_segments(&["abc".to_string()]).is_err());
+        assert_eq!(
+            Path::<(String, String)>::from_segments(&["x,y".to_string(), "[\"z\"]".to_string()]),
+            Ok(Path(("x,y".to_string(), "[\"z\"]".to_string())))
+        );
+        assert_eq!(
+            Path::<(String, u8)>::from_segments(&["players".to_string(), "a".to_string()])
+                .map_err(|(position, _)| position),
+            Err(1)
+        );
+        assert_eq!(
+            Path::<(u8,)>::from_segments(&["7".to_string()]),
+            Ok(Path((7,)))
+        );
+        assert!(Path::<u8>::from_segments(&["7".to_string(), "8".to_string()]).is_err());
+    }
+}
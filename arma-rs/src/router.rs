@@ -0,0 +1,273 @@
+//! Mounts and unmounts groups while the extension is running.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, RwLock},
+};
+
+use crate::{CommandInfo, Group, TreeError};
+
+#[derive(Clone)]
+/// Mounts and unmounts groups at the root of the extension while it is running,
+/// returned by [`ExtensionBuilder::router`](crate::ExtensionBuilder::router).
+///
+/// Mounted groups are called and listed like the groups added when building the extension.
+/// A call already running in a group finishes when the group is unmounted,
+/// so a command can mount or unmount groups, including its own.
+/// The router can be sent to other threads, such as a task deciding when to enable a module.
+/// Groups mounted before the extension is built are mounted by
+/// [`ExtensionBuilder::finish`](crate::ExtensionBuilder::finish).
+///
+/// ```
+/// # use arma_rs::{Extension, Group};
+/// let builder = Extension::build();
+/// let router = builder.router();
+/// let extension = builder
+///     .group(
+///         "modules",
+///         Group::new().command("enable", move || {
+///             router
+///                 .mount("ace", Group::new().command("ping", || "pong"))
+///                 .is_ok()
+///         }),
+///     )
+///     .finish();
+/// ```
+pub struct Router {
+    mounts: Arc<RwLock<Mounts>>,
+}
+
+impl Router {
+    pub(crate) fn new(mounts: Arc<RwLock<Mounts>>) -> Self {
+        Self { mounts }
+    }
+
+    /// Mounts `group` at `name`, called from Arma using `[name]:[command]`.
+    ///
+    /// # Errors
+    /// Returns the problems of the group, [`TreeError::DuplicateGroup`] if a group is
+    /// already added or mounted at `name`, ignoring case if the extension is case insensitive,
+    /// or [`TreeError::Shadowed`] with the commands of the extension starting with `name`,
+    /// such as `vehicle:{id}:fuel` for `vehicle`. The group is not mounted if there is any problem.
+    pub fn mount<S>(&self, name: S, group: Group) -> Result<(), Vec<TreeError>>
+    where
+        S: Into<String>,
+    {
+        self.mounts.write().unwrap().mount(name.into(), group)
+    }
+
+    /// Unmounts the group at `name`, returns if a group was mounted there.
+    /// Groups added when building the extension can not be unmounted.
+    pub fn unmount(&self, name: &str) -> bool {
+        self.mounts.write().unwrap().unmount(name)
+    }
+
+    #[must_use]
+    /// Returns the names of the mounted groups, sorted.
+    pub fn mounted(&self) -> Vec<String> {
+        self.mounts.read().unwrap().names()
+    }
+}
+
+#[derive(Default)]
+/// The mounted groups, shared by the routers and the root group of the extension.
+pub(crate) struct Mounts {
+    groups: BTreeMap<String, Arc<Group>>,
+    pending: Vec<(String, Group)>,
+    config: Option<Config>,
+}
+
+struct Config {
+    separator: char,
+    case_insensitive: bool,
+    /// The groups added when building the extension.
+    reserved: HashSet<String>,
+    /// The first segment and the name of the routes added when building the extension,
+    /// a group mounted at that segment would handle their calls.
+    routes: Vec<(String, String)>,
+}
+
+impl Mounts {
+    /// Mounts the groups mounted before the extension was built, returning their problems.
+    pub(crate) fn start(
+        &mut self,
+        separator: char,
+        case_insensitive: bool,
+        reserved: HashSet<String>,
+        routes: Vec<(String, String)>,
+    ) -> Vec<TreeError> {
+        self.config = Some(Config {
+            separator,
+            case_insensitive,
+            reserved,
+            routes,
+        });
+        let mut errors = Vec::new();
+        for (name, group) in std::mem::take(&mut self.pending) {
+            if let Err(e) = self.mount(name, group) {
+                errors.extend(e);
+            }
+        }
+        errors
+    }
+
+    fn mount(&mut self, name: String, mut group: Group) -> Result<(), Vec<TreeError>> {
+        let Some(config) = &self.config else {
+            self.pending.push((name, group));
+            return Ok(());
+        };
+        let same = |other: &str| {
+            other == name || (config.case_insensitive && other.eq_ignore_ascii_case(&name))
+        };
+        let mut errors = Vec::new();
+        if name.is_empty() {
+            errors.push(TreeError::EmptyName(name.clone()));
+        } else if name.contains(config.separator) {
+            errors.push(TreeError::Separator(name.clone()));
+        } else if config
+            .reserved
+            .iter()
+            .chain(self.groups.keys())
+            .any(|other| same(other))
+        {
+            errors.push(TreeError::DuplicateGroup(name.clone()));
+        } else {
+            errors.extend(
+                config
+                    .routes
+                    .iter()
+                    .filter(|(first, _)| same(first))
+                    .map(|(_, route)| TreeError::Shadowed(route.clone())),
+            );
+        }
+        group.configure(
+            &format!("{}{}", name, config.separator),
+            config.separator,
+            config.case_insensitive,
+        );
+        group.validate(&format!("{}{}", name, config.separator), &mut errors);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        self.groups.insert(name, Arc::new(group));
+        Ok(())
+    }
+
+    fn unmount(&mut self, name: &str) -> bool {
+        let pending = self.pending.len();
+        self.pending.retain(|(pending, _)| pending != name);
+        self.groups.remove(name).is_some() || self.pending.len() != pending
+    }
+
+    fn names(&self) -> Vec<String> {
+        let mut names = self
+            .groups
+            .keys()
+            .cloned()
+            .chain(self.pending.iter().map(|(name, _)| name.clone()))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Finds the group mounted at `name`, returning the name it was mounted with.
+    pub(crate) fn get(&self, name: &str) -> Option<(String, Arc<Group>)> {
+        self.groups
+            .get_key_value(name)
+            .or_else(|| match &self.config {
+                Some(config) if config.case_insensitive => self
+                    .groups
+                    .iter()
+                    .find(|(mounted, _)| mounted.eq_ignore_ascii_case(name)),
+                _ => None,
+            })
+            .map(|(name, group)| (name.clone(), group.clone()))
+    }
+
+    /// Adds the commands of the mounted groups to `commands`,
+    /// with the error codes of the middleware around them.
+    pub(crate) fn commands(
+        &self,
+        prefix: &str,
+        errors: &[(libc::c_int, String)],
+        commands: &mut Vec<CommandInfo>,
+    ) {
+        if let Some(config) = &self.config {
+            for (name, group) in &self.groups {
+                group.commands(
+                    &format!("{}{}{}", prefix, name, config.separator),
+                    errors,
+                    commands,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending() {
+        let mut mounts = Mounts::default();
+        assert!(mounts.mount("a".to_string(), Group::new()).is_ok());
+        assert!(mounts.mount("b".to_string(), Group::new()).is_ok());
+        assert!(mounts.mount("c:d".to_string(), Group::new()).is_ok());
+        assert!(mounts.unmount("b"));
+        assert_eq!(mounts.names(), ["a", "c:d"]);
+        let errors = mounts.start(':', false, HashSet::from(["a".to_string()]), Vec::new());
+        assert_eq!(
+            errors,
+            vec![
+                TreeError::DuplicateGroup("a".to_string()),
+                TreeError::Separator("c:d".to_string()),
+            ]
+        );
+        assert!(mounts.names().is_empty());
+        assert!(mounts.mount("A".to_string(), Group::new()).is_ok());
+        assert!(mounts.get("A").is_some());
+        assert!(mounts.get("a").is_none());
+    }
+
+    #[test]
+    fn routes() {
+        let mut mounts = Mounts::default();
+        mounts.start(
+            ':',
+            false,
+            HashSet::new(),
+            vec![("vehicle".to_string(), "vehicle:{id}:fuel".to_string())],
+        );
+        assert_eq!(
+            mounts.mount("vehicle".to_string(), Group::new()),
+            Err(vec![TreeError::Shadowed("vehicle:{id}:fuel".to_string())])
+        );
+        assert!(mounts.mount("Vehicle".to_string(), Group::new()).is_ok());
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let mut mounts = Mounts::default();
+        mounts.start(
+            ':',
+            true,
+            HashSet::from(["timer".to_string()]),
+            vec![("vehicle".to_string(), "vehicle:{id}:fuel".to_string())],
+        );
+        assert!(mounts.mount("ace".to_string(), Group::new()).is_ok());
+        assert_eq!(
+            mounts.mount("Timer".to_string(), Group::new()),
+            Err(vec![TreeError::DuplicateGroup("Timer".to_string())])
+        );
+        assert_eq!(
+            mounts.mount("ACE".to_string(), Group::new()),
+            Err(vec![TreeError::DuplicateGroup("ACE".to_string())])
+        );
+        assert_eq!(
+            mounts.mount("VEHICLE".to_string(), Group::new()),
+            Err(vec![TreeError::Shadowed("vehicle:{id}:fuel".to_string())])
+        );
+        assert_eq!(mounts.names(), ["ace"]);
+    }
+}
@@ -0,0 +1,266 @@
+//! Exports the commands of an extension as a JSON document.
+
+use std::collections::BTreeMap;
+
+use crate::CommandInfo;
+
+#[derive(Default)]
+struct Node<'a> {
+    /// The commands and their names in the group.
+    commands: Vec<(String, &'a CommandInfo)>,
+    groups: BTreeMap<&'a str, Node<'a>>,
+}
+
+/// Writes the schema of `commands`, which must be sorted by path.
+/// Paths are split into groups at `separator`, up to the first path parameter,
+/// `vehicle:{id}:fuel` is the command `{id}:fuel` of the group `vehicle`.
+pub(crate) fn to_json(version: &str, separator: char, commands: &[CommandInfo]) -> String {
+    let mut root = Node::default();
+    for command in commands {
+        let mut node = &mut root;
+        let segments = command.path().split(separator).collect::<Vec<_>>();
+        let literal = segments
+            .iter()
+            .position(|segment| is_parameter(segment))
+            .unwrap_or(segments.len() - 1);
+        for segment in &segments[..literal] {
+            node = node.groups.entry(*segment).or_default();
+        }
+        let name = segments[literal..].join(&separator.to_string());
+        node.commands.push((name, command));
+    }
+    let mut json = format!("{{\"version\":{},", string(version));
+    write_node(&mut json, separator, &root);
+    json.push('}');
+    json
+}
+
+fn write_node(json: &mut String, separator: char, node: &Node) {
+    json.push_str("\"commands\":[");
+    for (index, (name, command)) in node.commands.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        write_command(json, separator, name, command);
+    }
+    json.push_str("],\"groups\":[");
+    for (index, (name, group)) in node.groups.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!("{{\"name\":{},", string(name)));
+        write_node(json, separator, group);
+        json.push('}');
+    }
+    json.push(']');
+}
+
+fn write_command(json: &mut String, separator: char, name: &str, command: &CommandInfo) {
+    let doc = command.doc();
+    json.push_str(&format!(
+        "{{\"name\":{},\"path\":{},\"description\":{},\"deprecated\":{},\"aliases\":[{}],\"returns\":{},\"params\":[",
+        string(name),
+        string(command.path()),
+        optional(doc.description()),
+        optional(command.deprecated()),
+        command
+            .aliases()
+            .iter()
+            .map(|alias| string(alias))
+            .collect::<Vec<_>>()
+            .join(","),
+        optional(command.returns()),
+    ));
+    let captures = path_parameters(command, separator);
+    for (index, (name, ty)) in captures.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "{{\"name\":{},\"type\":{},\"optional\":false,\"variadic\":false,\"description\":null}}",
+            string(name),
+            string(ty),
+        ));
+    }
+    for (index, ty) in command.args().iter().enumerate() {
+        if index > 0 || !captures.is_empty() {
+            json.push(',');
+        }
+        let (name, description) = doc.arg_doc(index).map_or_else(
+            || (format!("arg{}", index), None),
+            |(n, d)| (n.to_string(), d),
+        );
+        // Commands take a fixed number of arguments, none are optional or variadic.
+        json.push_str(&format!(
+            "{{\"name\":{},\"type\":{},\"optional\":false,\"variadic\":false,\"description\":{}}}",
+            string(&name),
+            string(ty),
+            optional(description),
+        ));
+    }
+    json.push_str("],\"errors\":[");
+    for (index, (code, description)) in errors(command).iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "{{\"code\":{},\"description\":{}}}",
+            string(code),
+            string(description)
+        ));
+    }
+    json.push_str("]}");
+}
+
+/// The error codes a command can return, built-in codes first, then the codes of the middleware
+/// around it and the documented codes.
+fn errors(command: &CommandInfo) -> Vec<(String, String)> {
+    let mut errors = vec![(
+        "2x".to_string(),
+        "Invalid argument count, x is received count".to_string(),
+    )];
+    for (index, ty) in command.args().iter().enumerate() {
+        errors.push((
+            format!("3{}", index),
+            format!("Invalid argument type, expected {}", ty),
+        ));
+    }
+    for (index, ty) in command.path_params().iter().enumerate() {
+        errors.push((
+            format!("7{}", index),
+            format!("Invalid path parameter, expected {}", ty),
+        ));
+    }
+    if let Some(returns) = command.returns() {
+        errors.push((
+            "4".to_string(),
+            "Attempted to write a value larger than the buffer".to_string(),
+        ));
+        if returns.starts_with("Result<") {
+            errors.push(("9".to_string(), "Application error".to_string()));
+        }
+    }
+    for (code, description) in command.errors() {
+        if !errors.iter().any(|(c, _)| c == code) {
+            errors.push((code.clone(), description.clone()));
+        }
+    }
+    for (code, description) in command.doc().errors() {
+        match errors.iter_mut().find(|(c, _)| c == code) {
+            Some(error) => error.1.clone_from(description),
+            None => errors.push((code.clone(), description.clone())),
+        }
+    }
+    errors
+}
+
+/// The names and types of the path parameters of a command, listed before its arguments.
+fn path_parameters(command: &CommandInfo, separator: char) -> Vec<(&str, &str)> {
+    command
+        .path()
+        .split(separator)
+        .filter(|segment| is_parameter(segment))
+        .enumerate()
+        .map(|(index, segment)| {
+            let ty = command
+                .path_params()
+                .get(index)
+                .map_or("String", String::as_str);
+            (&segment[1..segment.len() - 1], ty)
+        })
+        .collect()
+}
+
+fn is_parameter(segment: &str) -> bool {
+    segment.starts_with('{') && segment.ends_with('}')
+}
+
+fn optional(value: Option<&str>) -> String {
+    value.map_or_else(|| "null".to_string(), string)
+}
+
+/// Quotes and escapes a JSON string.
+fn string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if u32::from(c) < 0x20 => json.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommandDoc;
+
+    #[test]
+    fn escape() {
+        assert_eq!(string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    }
+
+    #[test]
+    fn tree() {
+        let commands = [
+            CommandInfo::new(
+                "db:get".to_string(),
+                &["alloc::string::String"],
+                Some("core::result::Result<alloc::string::String, alloc::string::String>"),
+                CommandDoc::new("Reads a key")
+                    .arg("key", "The key to read")
+                    .error(9, "The key does not exist"),
+            ),
+            CommandInfo::new("ping".to_string(), &[], None, CommandDoc::default()),
+        ];
+        assert_eq!(
+            to_json("1.0.0", ':', &commands),
+            concat!(
+                r#"{"version":"1.0.0","commands":["#,
+                r#"{"name":"ping","path":"ping","description":null,"deprecated":null,"aliases":[],"returns":null,"params":[],"errors":[{"code":"2x","description":"Invalid argument count, x is received count"}]}"#,
+                r#"],"groups":[{"name":"db","commands":["#,
+                r#"{"name":"get","path":"db:get","description":"Reads a key","deprecated":null,"aliases":[],"returns":"Result<String, String>","#,
+                r#""params":[{"name":"key","type":"String","optional":false,"variadic":false,"description":"The key to read"}],"#,
+                r#""errors":[{"code":"2x","description":"Invalid argument count, x is received count"},"#,
+                r#"{"code":"30","description":"Invalid argument type, expected String"},"#,
+                r#"{"code":"4","description":"Attempted to write a value larger than the buffer"},"#,
+                r#"{"code":"9","description":"The key does not exist"}]}"#,
+                r#"],"groups":[]}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn routes() {
+        let commands = [CommandInfo::new(
+            "db:{table}:{id}:set".to_string(),
+            &["alloc::string::String"],
+            None,
+            CommandDoc::default(),
+        )
+        .with_path_params(&["alloc::string::String", "u32"])];
+        assert_eq!(
+            to_json("1.0.0", ':', &commands),
+            concat!(
+                r#"{"version":"1.0.0","commands":[],"groups":[{"name":"db","commands":["#,
+                r#"{"name":"{table}:{id}:set","path":"db:{table}:{id}:set","description":null,"deprecated":null,"aliases":[],"returns":null,"#,
+                r#""params":[{"name":"table","type":"String","optional":false,"variadic":false,"description":null},"#,
+                r#"{"name":"id","type":"u32","optional":false,"variadic":false,"description":null},"#,
+                r#"{"name":"arg0","type":"String","optional":false,"variadic":false,"description":null}],"#,
+                r#""errors":[{"code":"2x","description":"Invalid argument count, x is received count"},"#,
+                r#"{"code":"30","description":"Invalid argument type, expected String"},"#,
+                r#"{"code":"70","description":"Invalid path parameter, expected String"},"#,
+                r#"{"code":"71","description":"Invalid path parameter, expected u32"}]}"#,
+                r#"],"groups":[]}]}"#,
+            )
+        );
+    }
+}
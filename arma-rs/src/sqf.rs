@@ -0,0 +1,392 @@
+//! Generates SQF wrapper functions for the commands of an extension.
+
+use std::{collections::HashSet, io, path::Path};
+
+use crate::{CommandInfo, Extension};
+
+/// Generates one SQF function per command and a `CfgFunctions.hpp` registering them.
+///
+/// Each function checks its parameters with `params`, calls the extension,
+/// logs and returns `nil` when the command fails, and converts the output to the return type.
+/// The command `timer:sleep` becomes `TAG_fnc_timer_sleep` in `fn_timer_sleep.sqf`,
+/// paths are split at the [`Extension::separator`]. Path parameters are marked with `p_`,
+/// `vehicle:{id}:fuel` becomes `TAG_fnc_vehicle_p_id_fuel`.
+/// Commands that would still share a function name, which SQF compares ignoring case,
+/// get a number added to their name, such as `TAG_fnc_a_b_2`.
+///
+/// ```
+/// # use arma_rs::{Extension, Group, SqfGenerator};
+/// let extension = Extension::build()
+///     .group("timer", Group::new().command("sleep", |_: u32| {}))
+///     .finish();
+/// let files = SqfGenerator::new("my_extension", "MYX")
+///     .path("\\x\\my_mod\\addons\\main\\functions")
+///     .generate(&extension);
+/// assert_eq!(files[0].0, "fn_timer_sleep.sqf");
+/// assert_eq!(files[1].0, "CfgFunctions.hpp");
+/// ```
+pub struct SqfGenerator {
+    extension: String,
+    tag: String,
+    category: String,
+    path: Option<String>,
+}
+
+impl SqfGenerator {
+    #[must_use]
+    /// Creates a generator for the extension loaded as `extension`,
+    /// naming the functions `<tag>_fnc_<command>`.
+    pub fn new<S1, S2>(extension: S1, tag: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        let extension = extension.into();
+        Self {
+            category: extension.clone(),
+            extension,
+            tag: tag.into(),
+            path: None,
+        }
+    }
+
+    #[must_use]
+    /// Sets the `CfgFunctions` category of the functions, the extension name by default.
+    pub fn category<S>(mut self, category: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.category = category.into();
+        self
+    }
+
+    #[must_use]
+    /// Sets the folder the functions are loaded from, such as `\x\my_mod\addons\main\functions`.
+    pub fn path<S>(mut self, path: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.path = Some(path.into());
+        self
+    }
+
+    #[must_use]
+    /// Returns the file names and contents of the functions, followed by `CfgFunctions.hpp`.
+    pub fn generate(&self, extension: &Extension) -> Vec<(String, String)> {
+        let separator = extension.separator();
+        let commands = extension.commands();
+        let names = function_names(&commands, separator);
+        let mut files = commands
+            .iter()
+            .zip(&names)
+            .map(|(command, name)| {
+                (
+                    format!("fn_{}.sqf", name),
+                    self.function(command, separator),
+                )
+            })
+            .collect::<Vec<_>>();
+        files.push(("CfgFunctions.hpp".to_string(), self.cfg_functions(&names)));
+        files
+    }
+
+    /// Writes the generated files to `dir`.
+    ///
+    /// # Errors
+    /// Returns an error if a file can not be written.
+    pub fn write<P>(&self, extension: &Extension, dir: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        std::fs::create_dir_all(dir.as_ref())?;
+        for (name, content) in self.generate(extension) {
+            std::fs::write(dir.as_ref().join(name), content)?;
+        }
+        Ok(())
+    }
+
+    fn function(&self, command: &CommandInfo, separator: char) -> String {
+        let captures = path_parameters(command.path(), separator);
+        let names = (0..command.arity())
+            .map(|index| {
+                command.doc().arg_doc(index).map_or_else(
+                    || format!("_arg{}", index),
+                    |(name, _)| format!("_{}", name),
+                )
+            })
+            .collect::<Vec<_>>();
+        let params = command
+            .args()
+            .iter()
+            .zip(&names)
+            .map(|(ty, name)| {
+                sqf_default(ty).map_or_else(
+                    || format!("\"{}\"", name),
+                    |default| format!("[\"{}\", {}, [{}]]", name, default, default),
+                )
+            })
+            .collect::<Vec<_>>();
+        let params = captures
+            .iter()
+            .map(|name| format!("\"_{}\"", name))
+            .chain(params)
+            .collect::<Vec<_>>();
+        let function = if captures.is_empty() {
+            format!("\"{}\"", command.path())
+        } else {
+            let mut index = 0;
+            let format = command
+                .path()
+                .split(separator)
+                .map(|segment| {
+                    if is_parameter(segment) {
+                        index += 1;
+                        format!("%{}", index)
+                    } else {
+                        segment.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(&separator.to_string());
+            let captures = captures
+                .iter()
+                .map(|name| format!("_{}", name))
+                .collect::<Vec<_>>();
+            format!("format [\"{}\", {}]", format, captures.join(", "))
+        };
+
+        let mut sqf = format!(
+            "// Generated by arma-rs, calls \"{}\" on {}\n",
+            command.path(),
+            self.extension
+        );
+        if let Some(description) = command.doc().description() {
+            sqf.push_str(&format!("// {}\n", description));
+        }
+        sqf.push_str(&format!(
+            "\nparams [{}];\n\n\
+             (\"{}\" callExtension [{}, [{}]]) params [\"_output\", \"_code\", \"_error\"];\n\
+             if (_error != 0 || {{_code != 0}}) exitWith {{\n    \
+             diag_log format [\"{}: {} failed with code %1 (%2): %3\", _code, _error, _output];\n    \
+             nil\n\
+             }};\n",
+            params.join(", "),
+            self.extension,
+            function,
+            names.join(", "),
+            self.extension,
+            command.path(),
+        ));
+        if let Some(returns) = command.returns() {
+            sqf.push_str(&parse_output(returns));
+            sqf.push('\n');
+        }
+        sqf
+    }
+
+    fn cfg_functions(&self, names: &[String]) -> String {
+        let mut hpp = format!(
+            "// Generated by arma-rs, include in class CfgFunctions\nclass {} {{\n    class {} {{\n",
+            self.tag, self.category
+        );
+        if let Some(path) = &self.path {
+            hpp.push_str(&format!("        file = \"{}\";\n", path));
+        }
+        for name in names {
+            hpp.push_str(&format!("        class {} {{}};\n", name));
+        }
+        hpp.push_str("    };\n};\n");
+        hpp
+    }
+}
+
+/// The function names of `commands`, numbering the names already taken by an earlier command.
+fn function_names(commands: &[CommandInfo], separator: char) -> Vec<String> {
+    let mut taken = HashSet::new();
+    commands
+        .iter()
+        .map(|command| {
+            let base = function_name(command.path(), separator);
+            let mut name = base.clone();
+            let mut number = 1;
+            while !taken.insert(name.to_lowercase()) {
+                number += 1;
+                name = format!("{}_{}", base, number);
+            }
+            if number > 1 {
+                warn!(
+                    "the SQF function name of {} is already taken, using {}",
+                    command.path(),
+                    name
+                );
+            }
+            name
+        })
+        .collect()
+}
+
+/// The function name of a command, `timer:sleep` becomes `timer_sleep`
+/// and `vehicle:{id}:fuel` becomes `vehicle_p_id_fuel`.
+/// Other characters that can not be used in a function name, such as `:` in a command name, become `_`.
+fn function_name(path: &str, separator: char) -> String {
+    path.split(separator)
+        .map(|segment| {
+            if is_parameter(segment) {
+                format!("p_{}", &segment[1..segment.len() - 1])
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// The names of the path parameters of a command, passed before its arguments.
+fn path_parameters(path: &str, separator: char) -> Vec<&str> {
+    path.split(separator)
+        .filter(|segment| is_parameter(segment))
+        .map(|segment| &segment[1..segment.len() - 1])
+        .collect()
+}
+
+fn is_parameter(segment: &str) -> bool {
+    segment.starts_with('{') && segment.ends_with('}')
+}
+
+/// The default value of an argument, which is also used as the `params` type check.
+fn sqf_default(ty: &str) -> Option<&'static str> {
+    if is_string(ty) {
+        Some("\"\"")
+    } else if is_number(ty) {
+        Some("0")
+    } else if ty == "bool" {
+        Some("false")
+    } else if is_array(ty) {
+        Some("[]")
+    } else {
+        None
+    }
+}
+
+/// The expression converting `_output` to the return type.
+fn parse_output(ty: &str) -> String {
+    if let Some(inner) = strip_generic(ty, "Result<") {
+        // Errors exit early, the output is the `Ok` value.
+        return parse_output(first_generic(inner));
+    }
+    if let Some(inner) = strip_generic(ty, "Option<") {
+        return format!(
+            "if (_output == \"null\") exitWith {{nil}};\n{}",
+            parse_output(inner)
+        );
+    }
+    if is_string(ty) {
+        "_output".to_string()
+    } else if is_number(ty) {
+        "parseNumber _output".to_string()
+    } else if ty == "bool" {
+        "_output == \"true\"".to_string()
+    } else if is_array(ty) {
+        "parseSimpleArray _output".to_string()
+    } else {
+        "_output".to_string()
+    }
+}
+
+/// The first type of a list of generic arguments, `Vec<(u8, u8)>, String` returns `Vec<(u8, u8)>`.
+fn first_generic(args: &str) -> &str {
+    let mut depth = 0;
+    for (index, c) in args.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => return args[..index].trim(),
+            _ => {}
+        }
+    }
+    args.trim()
+}
+
+fn strip_generic<'a>(ty: &'a str, prefix: &str) -> Option<&'a str> {
+    ty.strip_prefix(prefix)?.strip_suffix('>')
+}
+
+fn is_string(ty: &str) -> bool {
+    matches!(ty, "String" | "&str" | "char")
+}
+
+fn is_number(ty: &str) -> bool {
+    matches!(
+        ty,
+        "i8" | "i16"
+            | "i32"
+            | "i64"
+            | "i128"
+            | "isize"
+            | "u8"
+            | "u16"
+            | "u32"
+            | "u64"
+            | "u128"
+            | "usize"
+            | "f32"
+            | "f64"
+    )
+}
+
+fn is_array(ty: &str) -> bool {
+    ty.starts_with("Vec<")
+        || ty.starts_with("&[")
+        || ty.starts_with('[')
+        || ty.starts_with('(')
+        || ty.starts_with("HashMap<")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommandDoc;
+
+    #[test]
+    fn names() {
+        let commands = ["a:b", "a_b", "A:B", "vehicle:id:fuel", "vehicle:{id}:fuel"]
+            .map(|path| CommandInfo::new(path.to_string(), &[], None, CommandDoc::default()));
+        assert_eq!(
+            function_names(&commands, ':'),
+            [
+                "a_b",
+                "a_b_2",
+                "A_B_3",
+                "vehicle_id_fuel",
+                "vehicle_p_id_fuel"
+            ]
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(sqf_default("String"), Some("\"\""));
+        assert_eq!(sqf_default("u32"), Some("0"));
+        assert_eq!(sqf_default("bool"), Some("false"));
+        assert_eq!(sqf_default("Vec<i32>"), Some("[]"));
+        assert_eq!(sqf_default("(f64, String)"), Some("[]"));
+        assert_eq!(sqf_default("Uuid"), None);
+    }
+
+    #[test]
+    fn outputs() {
+        assert_eq!(parse_output("String"), "_output");
+        assert_eq!(parse_output("i32"), "parseNumber _output");
+        assert_eq!(
+            parse_output("Result<Vec<(u8, String)>, String>"),
+            "parseSimpleArray _output"
+        );
+        assert_eq!(
+            parse_output("Option<bool>"),
+            "if (_output == \"null\") exitWith {nil};\n_output == \"true\""
+        );
+    }
+}
@@ -1,12 +1,9 @@
-use std::{sync::Arc, time::Duration};
+use std::time::Duration;
 
-use crossbeam_queue::SegQueue;
-
-use crate::{Context, Value};
+use crate::{callback::Message, ArmaEvent, Caller, Context, Value};
 
 pub struct Extension {
     pub ext: crate::Extension,
-    callback_queue: Arc<SegQueue<(String, String, Option<Value>)>>,
 }
 
 const BUFFER_SIZE: libc::size_t = 10240; // The sized used by Arma 3 as of 2021-12-30
@@ -27,16 +24,13 @@ pub enum Result<T, E> {
 impl Extension {
     #[must_use]
     pub fn new(ext: crate::Extension) -> Self {
-        Self {
-            ext,
-            callback_queue: Arc::new(SegQueue::new()),
-        }
+        Self { ext }
     }
 
     #[must_use]
     /// Returns a context for simulating interactions with Arma
     pub fn context(&self) -> Context {
-        Context::new(self.callback_queue.clone()).with_buffer_size(BUFFER_SIZE)
+        self.ext.context().with_buffer_size(BUFFER_SIZE)
     }
 
     #[must_use]
@@ -45,6 +39,20 @@ impl Extension {
     /// # Safety
     /// This function is unsafe because it interacts with the C API.
     pub unsafe fn call(&self, function: &str, args: Option<Vec<String>>) -> (String, libc::c_int) {
+        self.call_as(Caller::new(), function, args)
+    }
+
+    #[must_use]
+    /// Call a function as `caller`, intended for tests
+    ///
+    /// # Safety
+    /// This function is unsafe because it interacts with the C API.
+    pub unsafe fn call_as(
+        &self,
+        caller: Caller,
+        function: &str,
+        args: Option<Vec<String>>,
+    ) -> (String, libc::c_int) {
         let mut output = [0; BUFFER_SIZE];
         let len = args.as_ref().map(|a| a.len().try_into().unwrap());
         let mut args_pointer = args.map(|v| {
@@ -52,8 +60,10 @@ impl Extension {
                 .map(|s| std::ffi::CString::new(s).unwrap().into_raw())
                 .collect::<Vec<*mut i8>>()
         });
-        let res = self.ext.group.handle(
-            self.context(),
+        let res = self.ext.dispatch(
+            self.context()
+                .with_call_id(self.ext.next_call_id())
+                .with_caller(caller),
             function,
             output.as_mut_ptr(),
             BUFFER_SIZE,
@@ -81,10 +91,14 @@ impl Extension {
     where
         F: Fn(&str, &str, Option<Value>) -> Result<T, E>,
     {
-        let queue = self.callback_queue.clone();
+        let queue = &self.ext.callback_queue;
         let start = std::time::Instant::now();
         loop {
-            if let Some((name, func, data)) = queue.pop() {
+            if let Some(Message {
+                name, func, data, ..
+            }) = queue.pop_timeout(timeout.saturating_sub(start.elapsed()))
+            {
+                queue.mark_delivered();
                 match handler(&name, &func, data) {
                     Result::Ok(value) => return Result::Ok(value),
                     Result::Err(error) => return Result::Err(error),
@@ -97,4 +111,45 @@ impl Extension {
             }
         }
     }
+
+    /// Create an event handler
+    ///
+    /// Works like `callback_handler`, but only receives callbacks with the name and
+    /// function of the event, converted into the event.
+    ///
+    /// # Panics
+    /// Panics if the data of a matching callback can not be converted into the event.
+    pub fn event_handler<E, F, T, Er>(&self, handler: F, timeout: Duration) -> Result<T, Er>
+    where
+        E: ArmaEvent,
+        F: Fn(E) -> Result<T, Er>,
+    {
+        self.callback_handler(
+            |name, func, data| {
+                if name != E::NAME || func != E::FUNCTION {
+                    return Result::Continue;
+                }
+                match E::from_data(data.unwrap_or(Value::Null)) {
+                    Ok(event) => handler(event),
+                    Err(e) => panic!("callback {}:{} was not a valid event: {}", name, func, e),
+                }
+            },
+            timeout,
+        )
+    }
+
+    #[must_use]
+    /// Waits for the next event of type `E`, returns `None` if the timeout was reached
+    ///
+    /// # Panics
+    /// Panics if the data of a matching callback can not be converted into the event.
+    pub fn recv_event<E>(&self, timeout: Duration) -> Option<E>
+    where
+        E: ArmaEvent,
+    {
+        match self.event_handler(Result::<E, ()>::Ok, timeout) {
+            Result::Ok(event) => Some(event),
+            _ => None,
+        }
+    }
 }
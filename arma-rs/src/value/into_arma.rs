@@ -321,7 +321,7 @@ impl Value {
     pub fn is_empty(&self) -> bool {
         match self {
             Self::Null => true,
-            Self::Number(n) => (*n as f64) == 0.0,
+            Self::Number(n) => *n == 0.0,
             Self::Array(a) => a.is_empty(),
             Self::Boolean(b) => !*b,
             Self::String(s) => s.is_empty(),
@@ -378,7 +378,7 @@ mod tests {
     fn as_vec() {
         let arr = Value::Array(vec![Value::String("hello".into())]);
         let v = arr.as_vec().unwrap();
-        let first_value = v.get(0).unwrap();
+        let first_value = v.first().unwrap();
         assert!(first_value.is_string());
         assert_eq!(first_value.to_string(), String::from("\"hello\""));
     }
@@ -0,0 +1,173 @@
+//! Warns about commands that block Arma for longer than their frame budget.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::{CallbackSender, Value};
+
+/// The callback name used to report commands over their budget.
+/// The function is the command path and the data is `[elapsed ms, budget ms, [args]]`.
+pub const BUDGET_CALLBACK: &str = "arma_rs:budget";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// How long commands may block Arma's simulation thread.
+///
+/// Configured with [`ExtensionBuilder::frame_budget`](crate::ExtensionBuilder::frame_budget).
+pub struct FrameBudget {
+    default: Option<Duration>,
+    commands: HashMap<String, Duration>,
+    report: bool,
+}
+
+impl FrameBudget {
+    #[must_use]
+    /// Sets the budget of every command.
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            default: Some(budget),
+            commands: HashMap::new(),
+            report: false,
+        }
+    }
+
+    #[must_use]
+    /// Only commands given a budget with [`FrameBudget::command`] are watched.
+    pub fn commands_only() -> Self {
+        Self {
+            default: None,
+            commands: HashMap::new(),
+            report: false,
+        }
+    }
+
+    #[must_use]
+    /// Sets the budget of the command at `path`, such as `group:command` or `vehicle:{id}:fuel`.
+    pub fn command<S>(mut self, path: S, budget: Duration) -> Self
+    where
+        S: Into<String>,
+    {
+        self.commands.insert(path.into(), budget);
+        self
+    }
+
+    #[must_use]
+    /// Reports commands over their budget to SQF as callbacks named [`BUDGET_CALLBACK`].
+    pub const fn report(mut self) -> Self {
+        self.report = true;
+        self
+    }
+
+    fn budget_for(&self, path: &str) -> Option<Duration> {
+        self.commands.get(path).copied().or(self.default)
+    }
+}
+
+/// Checks calls against their budget and counts the violations.
+pub(crate) struct Watchdog {
+    budget: FrameBudget,
+    violations: Mutex<HashMap<String, u64>>,
+    sender: CallbackSender,
+}
+
+impl Watchdog {
+    pub(crate) fn new(budget: FrameBudget, sender: CallbackSender) -> Self {
+        Self {
+            budget,
+            violations: Mutex::new(HashMap::new()),
+            sender,
+        }
+    }
+
+    /// Records a call that took `elapsed`.
+    /// The arguments are only read if the call was over its budget.
+    pub(crate) fn check<A>(&self, path: &str, elapsed: Duration, args: A)
+    where
+        A: FnOnce() -> Vec<String>,
+    {
+        let budget = match self.budget.budget_for(path) {
+            Some(budget) if elapsed > budget => budget,
+            _ => return,
+        };
+        *self
+            .violations
+            .lock()
+            .unwrap()
+            .entry(path.to_string())
+            .or_default() += 1;
+        let args = args();
+        warn!(
+            "{} took {:?}, over its budget of {:?}, args: {:?}",
+            path, elapsed, budget, args
+        );
+        if self.budget.report {
+            let _ = self.sender.callback(
+                BUDGET_CALLBACK,
+                path,
+                Some(vec![
+                    Value::Number(elapsed.as_secs_f64() * 1000.0),
+                    Value::Number(budget.as_secs_f64() * 1000.0),
+                    Value::Array(args.into_iter().map(Value::String).collect()),
+                ]),
+            );
+        }
+    }
+
+    pub(crate) fn violations(&self) -> BTreeMap<String, u64> {
+        self.violations
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, count)| (path.clone(), *count))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::callback::CallbackQueue;
+
+    #[test]
+    fn budget_for() {
+        let budget = FrameBudget::new(Duration::from_millis(5))
+            .command("slow:query", Duration::from_millis(50));
+        assert_eq!(budget.budget_for("fast"), Some(Duration::from_millis(5)));
+        assert_eq!(
+            budget.budget_for("slow:query"),
+            Some(Duration::from_millis(50))
+        );
+        let budget = FrameBudget::commands_only().command("slow", Duration::from_millis(1));
+        assert_eq!(budget.budget_for("fast"), None);
+    }
+
+    #[test]
+    fn check() {
+        let queue = Arc::new(CallbackQueue::default());
+        let watchdog = Watchdog::new(
+            FrameBudget::new(Duration::from_millis(5)).report(),
+            CallbackSender::new(queue.clone()),
+        );
+        watchdog.check("fast", Duration::from_millis(1), || unreachable!());
+        watchdog.check("slow", Duration::from_millis(10), || vec!["1".to_string()]);
+        assert_eq!(
+            watchdog.violations(),
+            BTreeMap::from([("slow".to_string(), 1)])
+        );
+        let message = queue.pop_timeout(Duration::ZERO).unwrap();
+        assert_eq!(message.name, BUDGET_CALLBACK);
+        assert_eq!(message.func, "slow");
+        assert_eq!(
+            message.data,
+            Some(Value::Array(vec![
+                Value::Number(10.0),
+                Value::Number(5.0),
+                Value::Array(vec![Value::String("1".to_string())])
+            ]))
+        );
+    }
+}
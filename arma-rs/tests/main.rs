@@ -1,4 +1,11 @@
-use arma_rs::{Context, Extension, Group};
+use std::time::Duration;
+
+use arma_rs::{
+    ArmaEvent, Batch, Call, CallbackError, Caller, CommandDoc, Context, Correlation, Extension,
+    FrameBudget, Group, Guard, Logger, Middleware, OverflowPolicy, Path, RateLimit, SqfGenerator,
+    TreeError, Value, BUDGET_CALLBACK, HELP_COMMAND, LOG_CALLBACK, RATE_LIMITED, STATS_COMMAND,
+    UNAUTHORIZED,
+};
 
 #[test]
 fn root_command() {
@@ -233,3 +240,1119 @@ fn application_error_err() {
     let (_, code) = unsafe { extension.call("hello", None) };
     assert_eq!(code, 9);
 }
+
+#[test]
+fn callback_queue_full() {
+    let extension = Extension::build()
+        .callback_capacity(1)
+        .callback_overflow(OverflowPolicy::Error)
+        .finish()
+        .testing();
+    let ctx = extension.context();
+    assert_eq!(ctx.try_callback("test", "first", Some("data")), Ok(()));
+    assert_eq!(
+        ctx.try_callback("test", "second", Some("data")),
+        Err(CallbackError::QueueFull)
+    );
+    assert_eq!(extension.ext.callback_stats().dropped, 1);
+}
+
+#[test]
+fn callback_batch() {
+    let extension = Extension::build()
+        .batch_callbacks(
+            "tracker",
+            "position",
+            Batch::window(Duration::from_secs(60)).max_len(3),
+        )
+        .finish()
+        .testing();
+    let ctx = extension.context();
+    for i in 0..3 {
+        ctx.callback("tracker", "position", Some(i));
+    }
+    let result = extension.callback_handler(
+        |name, func, data| {
+            assert_eq!(name, "tracker");
+            assert_eq!(func, "position");
+            arma_rs::Result::<_, ()>::Ok(data)
+        },
+        Duration::from_secs(1),
+    );
+    assert_eq!(
+        result,
+        arma_rs::Result::Ok(Some(Value::Array(vec![
+            Value::Number(0.0),
+            Value::Number(1.0),
+            Value::Number(2.0)
+        ])))
+    );
+}
+
+#[derive(Debug, PartialEq, ArmaEvent)]
+#[event(name = "timer", function = "done")]
+struct TimerDone {
+    id: String,
+    seconds: u32,
+}
+
+#[derive(Debug, PartialEq, ArmaEvent)]
+#[event(name = "timer", function = "started")]
+struct TimerStarted(String);
+
+#[test]
+fn emit_event() {
+    let extension = Extension::build()
+        .command("start", |ctx: Context, id: String| {
+            ctx.emit(TimerStarted(id.clone())).unwrap();
+            ctx.emit(TimerDone { id, seconds: 5 }).unwrap();
+        })
+        .finish()
+        .testing();
+    let (_, code) = unsafe { extension.call("start", Some(vec![String::from("test")])) };
+    assert_eq!(code, 0);
+    assert_eq!(
+        extension.recv_event::<TimerStarted>(Duration::from_secs(1)),
+        Some(TimerStarted(String::from("test")))
+    );
+    assert_eq!(
+        extension.recv_event::<TimerDone>(Duration::from_secs(1)),
+        Some(TimerDone {
+            id: String::from("test"),
+            seconds: 5
+        })
+    );
+}
+
+#[test]
+fn callback_sender() {
+    let builder = Extension::build();
+    let sender = builder.callback_sender();
+    let extension = builder.finish().testing();
+    std::thread::spawn(move || {
+        sender.callback("sender", "builder", None::<&str>).unwrap();
+    })
+    .join()
+    .unwrap();
+    let sender = extension.context().callback_sender();
+    sender.callback("sender", "context", None::<&str>).unwrap();
+    for expected in ["builder", "context"] {
+        let result = extension.callback_handler(
+            |name, func, _| {
+                assert_eq!(name, "sender");
+                arma_rs::Result::<_, ()>::Ok(func.to_string())
+            },
+            Duration::from_secs(1),
+        );
+        assert_eq!(result, arma_rs::Result::Ok(expected.to_string()));
+    }
+}
+
+#[test]
+fn correlate_callbacks() {
+    let extension = Extension::build()
+        .correlate_callbacks(Correlation::Function)
+        .command("request", |ctx: Context| -> String {
+            let sender = ctx.callback_sender();
+            std::thread::spawn(move || {
+                sender.callback("request", "done", Some("ok")).unwrap();
+            });
+            ctx.call_id().unwrap().to_string()
+        })
+        .finish()
+        .testing();
+    let (first, code) = unsafe { extension.call("request", None) };
+    assert_eq!(code, 0);
+    let (second, _) = unsafe { extension.call("request", None) };
+    assert_ne!(first, second);
+    let mut funcs = Vec::new();
+    for _ in 0..2 {
+        let result = extension.callback_handler(
+            |_, func, _| arma_rs::Result::<_, ()>::Ok(func.to_string()),
+            Duration::from_secs(1),
+        );
+        if let arma_rs::Result::Ok(func) = result {
+            funcs.push(func);
+        }
+    }
+    funcs.sort();
+    let mut expected = vec![format!("done:{}", first), format!("done:{}", second)];
+    expected.sort();
+    assert_eq!(funcs, expected);
+}
+
+#[test]
+fn logger_forward() {
+    let extension = Extension::build()
+        .logger(
+            Logger::new(log::LevelFilter::Info)
+                .module("main::quiet", log::LevelFilter::Error)
+                .forward(log::LevelFilter::Warn),
+        )
+        .command("log", || {
+            log::info!("not forwarded");
+            log::warn!(target: "main::quiet", "filtered");
+            log::warn!("forwarded");
+        })
+        .finish()
+        .testing();
+    let (_, code) = unsafe { extension.call("log", None) };
+    assert_eq!(code, 0);
+    let result = extension.callback_handler(
+        |name, func, data| {
+            assert_eq!(name, LOG_CALLBACK);
+            assert_eq!(func, "WARN");
+            arma_rs::Result::<_, ()>::Ok(data)
+        },
+        Duration::from_secs(1),
+    );
+    assert_eq!(
+        result,
+        arma_rs::Result::Ok(Some(Value::Array(vec![
+            Value::String("main".to_string()),
+            Value::String("forwarded".to_string())
+        ])))
+    );
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn tracing_spans() {
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    #[derive(Clone, Default)]
+    struct Recorder {
+        next: Arc<AtomicU64>,
+        fields: Arc<Mutex<Vec<String>>>,
+        events: Arc<Mutex<Vec<Option<u64>>>>,
+    }
+
+    struct Fields<'a>(&'a Mutex<Vec<String>>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            span.record(&mut Fields(&self.fields));
+            span::Id::from_u64(self.next.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+        fn record(&self, _: &span::Id, values: &span::Record<'_>) {
+            values.record(&mut Fields(&self.fields));
+        }
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, event: &Event<'_>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(event.parent().map(span::Id::into_u64));
+        }
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    let recorder = Recorder::default();
+    let extension = Extension::build()
+        .group(
+            "timer",
+            Group::new().command("start", |ctx: Context| {
+                ctx.callback("timer", "done", Some("ok"));
+            }),
+        )
+        .help_command()
+        .finish()
+        .testing();
+    let (_, code) = tracing::subscriber::with_default(recorder.clone(), || unsafe {
+        extension.call("timer:start", Some(vec![]))
+    });
+    assert_eq!(code, 0);
+    let (_, code) = tracing::subscriber::with_default(recorder.clone(), || unsafe {
+        extension.call(HELP_COMMAND, None)
+    });
+    assert_eq!(code, 0);
+    let fields = recorder.fields.lock().unwrap().clone();
+    for expected in [
+        "path=\"timer:start\"",
+        "path=\"arma_rs:help\"",
+        "args=0",
+        "buffer_size=10240",
+        "code=0",
+    ] {
+        assert!(fields.iter().any(|f| f == expected), "{:?}", fields);
+    }
+    assert!(fields.iter().any(|f| f.starts_with("duration_us=")));
+    assert_eq!(*recorder.events.lock().unwrap(), vec![Some(1)]);
+}
+
+#[test]
+fn metrics() {
+    let extension = Extension::build()
+        .metrics()
+        .group(
+            "math",
+            Group::new().command("double", |n: i32| -> i32 { n * 2 }),
+        )
+        .command("vehicle:{id}:fuel", |Path(id): Path<u32>| -> u32 { id })
+        .finish()
+        .testing();
+    unsafe {
+        let _ = extension.call("math:double", Some(vec![String::from("21")]));
+        let _ = extension.call("math:double", Some(vec![String::from("nope")]));
+        let _ = extension.call("math:missing", None);
+        let _ = extension.call("vehicle:1:fuel", None);
+        let _ = extension.call("vehicle:2:fuel", None);
+    }
+    let metrics = extension.ext.metrics();
+    assert_eq!(metrics.len(), 2);
+    assert_eq!(metrics["vehicle:{id}:fuel"].calls, 2);
+    let double = &metrics["math:double"];
+    assert_eq!(double.calls, 2);
+    assert_eq!(double.codes.get(&0), Some(&1));
+    assert_eq!(double.codes.get(&30), Some(&1));
+    assert_eq!(double.latency.iter().sum::<u64>(), 2);
+    assert_eq!(double.output_bytes, 2);
+
+    let (stats, code) = unsafe { extension.call(STATS_COMMAND, None) };
+    assert_eq!(code, 0);
+    assert!(stats.starts_with(r#"[["math:double",2,[[0,1],[30,1]],"#));
+}
+
+#[test]
+fn frame_budget() {
+    let extension = Extension::build()
+        .frame_budget(
+            FrameBudget::new(Duration::from_secs(1))
+                .command("slow", Duration::from_millis(5))
+                .report(),
+        )
+        .command("slow", |ms: u32| {
+            std::thread::sleep(Duration::from_millis(ms.into()));
+        })
+        .command("fast", || {})
+        .finish()
+        .testing();
+    let (_, code) = unsafe { extension.call("slow", Some(vec![String::from("20")])) };
+    assert_eq!(code, 0);
+    let (_, code) = unsafe { extension.call("fast", None) };
+    assert_eq!(code, 0);
+    assert_eq!(
+        extension
+            .ext
+            .budget_violations()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec![("slow".to_string(), 1)]
+    );
+    let result = extension.callback_handler(
+        |name, func, data| {
+            assert_eq!(name, BUDGET_CALLBACK);
+            assert_eq!(func, "slow");
+            arma_rs::Result::<_, ()>::Ok(data)
+        },
+        Duration::from_secs(1),
+    );
+    match result {
+        arma_rs::Result::Ok(Some(Value::Array(data))) => {
+            assert_eq!(data[1], Value::Number(5.0));
+            assert_eq!(data[2], Value::Array(vec![Value::String("20".to_string())]));
+        }
+        other => panic!("unexpected callback: {:?}", other),
+    }
+}
+
+#[test]
+fn middleware() {
+    use std::sync::{Arc, Mutex};
+
+    struct Record(&'static str, Arc<Mutex<Vec<String>>>);
+
+    impl Middleware for Record {
+        fn before(&self, call: &mut Call) -> Result<(), i32> {
+            self.1.lock().unwrap().push(format!(
+                "{} before {} {:?}",
+                self.0,
+                call.path(),
+                call.args()
+            ));
+            Ok(())
+        }
+
+        fn after(&self, call: &Call, code: i32, output: &str) {
+            self.1.lock().unwrap().push(format!(
+                "{} after {} {} {}",
+                self.0,
+                call.path(),
+                code,
+                output
+            ));
+        }
+    }
+
+    struct Sanitize;
+
+    impl Middleware for Sanitize {
+        fn before(&self, call: &mut Call) -> Result<(), i32> {
+            if call.args().iter().any(|arg| arg.contains(';')) {
+                return Err(9);
+            }
+            for arg in call.args_mut() {
+                *arg = arg.trim().to_string();
+            }
+            Ok(())
+        }
+    }
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let extension = Extension::build()
+        .middleware(Record("ext", log.clone()))
+        .group(
+            "text",
+            Group::new()
+                .middleware(Record("group", log.clone()))
+                .middleware(Sanitize)
+                .command("echo", |text: String| -> String { text }),
+        )
+        .finish()
+        .testing();
+    let (result, code) = unsafe { extension.call("text:echo", Some(vec![String::from(" hi ")])) };
+    assert_eq!(code, 0);
+    assert_eq!(result, "hi");
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            r#"ext before text:echo [" hi "]"#,
+            r#"group before text:echo [" hi "]"#,
+            "group after text:echo 0 hi",
+            "ext after text:echo 0 hi",
+        ]
+    );
+    log.lock().unwrap().clear();
+    let (_, code) = unsafe { extension.call("text:echo", Some(vec![String::from("a;b")])) };
+    assert_eq!(code, 9);
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            r#"ext before text:echo ["a;b"]"#,
+            r#"group before text:echo ["a;b"]"#,
+            "group after text:echo 9 ",
+            "ext after text:echo 9 ",
+        ]
+    );
+}
+
+#[test]
+fn rate_limit() {
+    let extension = Extension::build()
+        .group(
+            "db",
+            Group::new()
+                .middleware(RateLimit::new(2, Duration::from_secs(60)).per_caller())
+                .command("query", || {})
+                .command("insert", || {}),
+        )
+        .command("unlimited", || {})
+        .finish()
+        .testing();
+    let alice = Caller::new().with_steam_id("76561198000000001");
+    let bob = Caller::new().with_steam_id("76561198000000002");
+    unsafe {
+        assert_eq!(extension.call_as(alice.clone(), "db:query", None).1, 0);
+        assert_eq!(extension.call_as(alice.clone(), "db:insert", None).1, 0);
+        assert_eq!(
+            extension.call_as(alice.clone(), "db:query", None).1,
+            RATE_LIMITED
+        );
+        assert_eq!(extension.call_as(alice, "unlimited", None).1, 0);
+        assert_eq!(extension.call_as(bob, "db:query", None).1, 0);
+    }
+}
+
+#[test]
+fn rate_limit_commands() {
+    let extension = Extension::build()
+        .group(
+            "vehicle",
+            Group::new()
+                .middleware(RateLimit::new(1, Duration::from_secs(60)).per_command())
+                .command("{id}:fuel", |Path(_): Path<u32>| {})
+                .command("list", || {}),
+        )
+        .command("export", || {})
+        .rate_limit_command("export", RateLimit::new(1, Duration::from_secs(60)))
+        .command("ok", || {})
+        .finish()
+        .testing();
+    unsafe {
+        assert_eq!(extension.call("vehicle:nope:missing", None).1, 1);
+        assert_eq!(extension.call("vehicle:1:fuel", None).1, 0);
+        assert_eq!(extension.call("vehicle:2:fuel", None).1, RATE_LIMITED);
+        assert_eq!(extension.call("vehicle:list", None).1, 0);
+        assert_eq!(extension.call("export", None).1, 0);
+        assert_eq!(extension.call("export", None).1, RATE_LIMITED);
+        assert_eq!(extension.call("ok", None).1, 0);
+    }
+}
+
+#[test]
+fn context_command() {
+    let command = |ctx: Context| -> String { ctx.command().unwrap_or_default().to_string() };
+    let extension = Extension::build()
+        .command("vehicle:{id}:fuel", command)
+        .group(
+            "db",
+            Group::new().command("get", command).fallback(
+                |ctx: Context, _: &str, _: Vec<String>| {
+                    ctx.command().unwrap_or_default().to_string()
+                },
+            ),
+        )
+        .finish()
+        .testing();
+    unsafe {
+        assert_eq!(
+            extension.call("vehicle:1:fuel", None).0,
+            "vehicle:{id}:fuel"
+        );
+        assert_eq!(extension.call("db:get", None).0, "db:get");
+        assert_eq!(extension.call("db:tables", None).0, "db:*");
+    }
+}
+
+#[test]
+fn caller() {
+    let extension = Extension::build()
+        .command("whoami", |ctx: Context| -> String {
+            ctx.caller().steam_id().unwrap_or("server").to_string()
+        })
+        .finish()
+        .testing();
+    let (result, _) = unsafe { extension.call("whoami", None) };
+    assert_eq!(result, "server");
+    let (result, _) = unsafe {
+        extension.call_as(
+            Caller::new().with_steam_id("76561198000000001"),
+            "whoami",
+            None,
+        )
+    };
+    assert_eq!(result, "76561198000000001");
+}
+
+#[test]
+fn guards() {
+    const ADMIN: &str = "76561198000000001";
+    let extension = Extension::build()
+        .group(
+            "admin",
+            Group::new()
+                .guard(Guard::server().or(Guard::steam_ids([ADMIN])))
+                .command("kick", || {}),
+        )
+        .group(
+            "mission",
+            Group::new()
+                .guard_command("reset", Guard::file_sources(["x\\my_mod\\"]))
+                .command("reset", || {})
+                .command("status", || {}),
+        )
+        .finish()
+        .testing();
+    let player = Caller::new().with_steam_id("76561198000000002");
+    unsafe {
+        assert_eq!(extension.call("admin:kick", None).1, 0);
+        assert_eq!(
+            extension
+                .call_as(Caller::new().with_steam_id(ADMIN), "admin:kick", None)
+                .1,
+            0
+        );
+        assert_eq!(
+            extension.call_as(player.clone(), "admin:kick", None).1,
+            UNAUTHORIZED
+        );
+        assert_eq!(
+            extension.call_as(player.clone(), "mission:reset", None).1,
+            UNAUTHORIZED
+        );
+        assert_eq!(
+            extension
+                .call_as(
+                    player.clone().with_file_source("x\\my_mod\\fn_reset.sqf"),
+                    "mission:reset",
+                    None
+                )
+                .1,
+            0
+        );
+        assert_eq!(extension.call_as(player, "mission:status", None).1, 0);
+    }
+}
+
+#[test]
+fn guard_builtin_commands() {
+    let extension = Extension::build()
+        .help_command()
+        .metrics()
+        .guard(Guard::server())
+        .command("x", || {})
+        .finish()
+        .testing();
+    let player = Caller::new().with_steam_id("76561198000000002");
+    unsafe {
+        for command in ["x", HELP_COMMAND, STATS_COMMAND] {
+            let (output, code) = extension.call_as(player.clone(), command, None);
+            assert_eq!((output.as_str(), code), ("", UNAUTHORIZED));
+            assert_eq!(extension.call(command, None).1, 0);
+        }
+    }
+}
+
+#[test]
+fn commands() {
+    let extension = Extension::build()
+        .help_command()
+        .command("hello", || -> &'static str { "Hello" })
+        .group(
+            "timer",
+            Group::new()
+                .command("sleep", |_: Context, _: u32, _: String| {})
+                .group(
+                    "inner",
+                    Group::new().command("sum", |a: Vec<i32>| -> i32 { a.iter().sum() }),
+                ),
+        )
+        .finish()
+        .testing();
+    let commands = extension.ext.commands();
+    let paths = commands.iter().map(|c| c.path()).collect::<Vec<_>>();
+    assert_eq!(paths, vec!["hello", "timer:inner:sum", "timer:sleep"]);
+    assert_eq!(commands[0].arity(), 0);
+    assert_eq!(commands[0].returns(), Some("&str"));
+    assert_eq!(commands[1].args(), ["Vec<i32>"]);
+    assert_eq!(commands[1].returns(), Some("i32"));
+    assert_eq!(commands[2].args(), ["u32", "String"]);
+    assert_eq!(commands[2].returns(), None);
+
+    let (result, code) = unsafe { extension.call(HELP_COMMAND, None) };
+    assert_eq!(code, 0);
+    assert_eq!(
+        result,
+        r#"[["hello",[],"&str",[],""],["timer:inner:sum",["Vec<i32>"],"i32",[],""],["timer:sleep",["u32","String"],"",[],""]]"#
+    );
+}
+
+#[test]
+fn schema() {
+    let extension = Extension::build()
+        .version("1.2.3".to_string())
+        .command("hello", || -> &'static str { "Hello" })
+        .group(
+            "db",
+            Group::new()
+                .command("get", |key: String| -> Result<String, String> { Ok(key) })
+                .document(
+                    "get",
+                    CommandDoc::new("Reads a key")
+                        .arg("key", "The key to read")
+                        .error(9, "The key does not exist"),
+                ),
+        )
+        .finish();
+    let schema = extension.schema();
+    assert!(schema.starts_with(r#"{"version":"1.2.3","commands":[{"name":"hello","#));
+    assert!(schema.contains(r#"],"groups":[{"name":"db","commands":[{"name":"get","path":"db:get","description":"Reads a key","deprecated":null,"aliases":[],"returns":"Result<String, String>","#));
+    assert!(schema.contains(
+        r#""params":[{"name":"key","type":"String","optional":false,"variadic":false,"description":"The key to read"}]"#
+    ));
+    assert!(schema.contains(r#"{"code":"9","description":"The key does not exist"}"#));
+    assert!(schema.ends_with(r#"],"groups":[]}]}"#));
+
+    let extension = Extension::build()
+        .rate_limit_command("hello", RateLimit::new(1, Duration::from_secs(1)))
+        .command("hello", || -> &'static str { "Hello" })
+        .group(
+            "db",
+            Group::new()
+                .guard(Guard::server())
+                .command("get:{key}", |Path(key): Path<String>| -> String { key })
+                .document(
+                    "get:{key}",
+                    CommandDoc::new("Reads a \"key\",\n\tthen returns it \\ \u{1}"),
+                ),
+        )
+        .finish();
+    let schema: serde_json::Value = serde_json::from_str(&extension.schema()).unwrap();
+    let codes = |command: &serde_json::Value| {
+        command["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["code"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let hello = &schema["commands"][0];
+    assert_eq!(hello["path"], "hello");
+    assert_eq!(codes(hello), ["2x", "4", "5"]);
+    let get = &schema["groups"][0]["groups"][0]["commands"][0];
+    assert_eq!(get["path"], "db:get:{key}");
+    assert_eq!(
+        get["description"],
+        "Reads a \"key\",\n\tthen returns it \\ \u{1}"
+    );
+    assert_eq!(get["params"][0]["name"], "key");
+    assert_eq!(codes(get), ["2x", "70", "4", "6"]);
+}
+
+#[test]
+fn sqf_wrappers() {
+    let extension = Extension::build()
+        .group(
+            "db",
+            Group::new()
+                .command("get", |key: String| -> Result<Vec<String>, String> {
+                    Ok(vec![key])
+                })
+                .document("get", CommandDoc::new("Reads a key").arg("key", "The key")),
+        )
+        .command("count", |_: bool| -> u32 { 1 })
+        .finish();
+    let files = SqfGenerator::new("my_ext", "MYX")
+        .path("\\x\\my_mod\\functions")
+        .generate(&extension);
+    let names = files
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["fn_count.sqf", "fn_db_get.sqf", "CfgFunctions.hpp"]);
+    assert!(files[0]
+        .1
+        .contains(r#"params [["_arg0", false, [false]]];"#));
+    assert!(files[0].1.ends_with("parseNumber _output\n"));
+    assert!(files[1].1.contains("// Reads a key\n"));
+    assert!(files[1].1.contains(r#"params [["_key", "", [""]]];"#));
+    assert!(files[1]
+        .1
+        .contains(r#"("my_ext" callExtension ["db:get", [_key]])"#));
+    assert!(files[1].1.ends_with("parseSimpleArray _output\n"));
+    assert_eq!(
+        files[2].1,
+        "// Generated by arma-rs, include in class CfgFunctions\n\
+         class MYX {\n    class my_ext {\n        file = \"\\x\\my_mod\\functions\";\n        \
+         class count {};\n        class db_get {};\n    };\n};\n"
+    );
+}
+
+#[arma_rs::command(group = "auto:math", name = "add")]
+fn auto_add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[arma_rs::command]
+fn auto_hello() -> &'static str {
+    "Hello"
+}
+
+#[test]
+fn auto_commands() {
+    let extension = Extension::build()
+        .command("auto_hello", || -> &'static str { "Manual" })
+        .auto_commands()
+        .finish()
+        .testing();
+    let (result, code) = unsafe {
+        extension.call(
+            "auto:math:add",
+            Some(vec!["1".to_string(), "2".to_string()]),
+        )
+    };
+    assert_eq!(code, 0);
+    assert_eq!(result, "3");
+    let (result, _) = unsafe { extension.call("auto_hello", None) };
+    assert_eq!(result, "Manual");
+
+    let extension = Extension::build().auto_commands().finish().testing();
+    let (result, _) = unsafe { extension.call("auto_hello", None) };
+    assert_eq!(result, "Hello");
+}
+
+#[test]
+fn tree_validation() {
+    let errors = Extension::build()
+        .help_command()
+        .command("hello", || {})
+        .command("hello", || {})
+        .command("", || {})
+        .group(
+            "hello",
+            Group::new()
+                .group("bad:name", Group::new())
+                .group("inner", Group::new())
+                .group("inner", Group::new()),
+        )
+        .group("arma_rs", Group::new().command("help", || {}))
+        .try_finish()
+        .err()
+        .unwrap();
+    assert_eq!(
+        errors,
+        vec![
+            TreeError::DuplicateCommand("hello".to_string()),
+            TreeError::EmptyName("".to_string()),
+            TreeError::DuplicateGroup("hello:inner".to_string()),
+            TreeError::Separator("hello:bad:name".to_string()),
+            TreeError::Shadowed(HELP_COMMAND.to_string()),
+        ]
+    );
+    assert_eq!(
+        errors[3].to_string(),
+        "hello:bad:name contains the separator and can not be called"
+    );
+
+    // A command and a group can share a name
+    let extension = Extension::build()
+        .command("hello", || -> &'static str { "command" })
+        .group(
+            "hello",
+            Group::new().command("inner", || -> &'static str { "group" }),
+        )
+        .try_finish();
+    assert!(extension.is_ok());
+}
+
+#[test]
+fn fallback() {
+    let extension = Extension::build()
+        .group(
+            "db",
+            Group::new()
+                .command("tables", || -> u8 { 1 })
+                .fallback(
+                    |_: Context, rest: &str, args: Vec<String>| -> Result<String, String> {
+                        match rest.split_once(':') {
+                            Some((table, "get")) => Ok(format!("{} {}", table, args.join(","))),
+                            _ => Err(format!("unknown command {}", rest)),
+                        }
+                    },
+                )
+                .group("inner", Group::new()),
+        )
+        .finish()
+        .testing();
+    let (result, code) = unsafe { extension.call("db:tables", None) };
+    assert_eq!((result.as_str(), code), ("1", 0));
+    let (result, code) = unsafe {
+        extension.call(
+            "db:players:get",
+            Some(vec!["\"name\"".to_string(), "2".to_string()]),
+        )
+    };
+    assert_eq!((result.as_str(), code), (r#"players "name",2"#, 0));
+    let (result, code) = unsafe { extension.call("db:missing", None) };
+    assert_eq!((result.as_str(), code), ("unknown command missing", 9));
+    let (_, code) = unsafe { extension.call("db:inner:missing", None) };
+    assert_eq!(code, 1);
+    let (_, code) = unsafe { extension.call("missing", None) };
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn path_parameters() {
+    let extension = Extension::build()
+        .command("vehicle:{id}:fuel", |Path(id): Path<u32>| -> u32 {
+            id * 10
+        })
+        .group(
+            "db",
+            Group::new()
+                .command("tables", || -> &'static str { "tables" })
+                .command(
+                    "{table}:{id}:set",
+                    |_: Context, Path((table, id)): Path<(String, u8)>, value: String| -> String {
+                        format!("{} {} {}", table, id, value)
+                    },
+                )
+                .command("{table}:count", |Path(table): Path<String>| {
+                    assert_eq!(table, "players");
+                }),
+        )
+        .finish()
+        .testing();
+    let (result, code) = unsafe { extension.call("vehicle:42:fuel", None) };
+    assert_eq!((result.as_str(), code), ("420", 0));
+    let (_, code) = unsafe { extension.call("vehicle:abc:fuel", None) };
+    assert_eq!(code, 70);
+    let (_, code) = unsafe { extension.call("vehicle:42", None) };
+    assert_eq!(code, 1);
+    let (result, code) = unsafe { extension.call("db:tables", None) };
+    assert_eq!((result.as_str(), code), ("tables", 0));
+    let (result, code) =
+        unsafe { extension.call("db:players:7:set", Some(vec!["\"name\"".to_string()])) };
+    assert_eq!((result.as_str(), code), ("players 7 name", 0));
+    let (_, code) = unsafe { extension.call("db:players:7:set", None) };
+    assert_eq!(code, 20);
+    let (_, code) =
+        unsafe { extension.call("db:players:abc:set", Some(vec!["\"name\"".to_string()])) };
+    assert_eq!(code, 71);
+    let (_, code) = unsafe { extension.call("db:players:count", None) };
+    assert_eq!(code, 0);
+
+    let errors = Extension::build()
+        .command("vehicle:{id}:fuel", |Path(id): Path<u32>| -> u32 { id })
+        .group("vehicle", Group::new().command("list", || {}))
+        .try_finish()
+        .err()
+        .unwrap();
+    assert_eq!(
+        errors,
+        vec![TreeError::Shadowed("vehicle:{id}:fuel".to_string())]
+    );
+
+    let commands = extension.ext.commands();
+    assert_eq!(commands[2].path(), "db:{table}:{id}:set");
+    assert_eq!(commands[2].args(), ["String"]);
+    assert_eq!(commands[2].path_params(), ["String", "u8"]);
+    assert!(extension.ext.schema().contains(
+        r#"{"name":"vehicle","commands":[{"name":"{id}:fuel","path":"vehicle:{id}:fuel","#
+    ));
+    let files = SqfGenerator::new("my_ext", "MYX").generate(&extension.ext);
+    assert_eq!(files[2].0, "fn_db_p_table_p_id_set.sqf");
+    assert!(files[2]
+        .1
+        .contains(r#"params ["_table", "_id", ["_arg0", "", [""]]];"#));
+    assert!(files[2]
+        .1
+        .contains(r#"("my_ext" callExtension [format ["db:%1:%2:set", _table, _id], [_arg0]])"#));
+}
+
+#[test]
+fn aliases() {
+    let extension = Extension::build()
+        .group(
+            "hello",
+            Group::new()
+                .command("english", || -> &'static str { "Hello" })
+                .alias("en", "english")
+                .deprecated("en", "use hello:english")
+                .group(
+                    "Inner",
+                    Group::new().command("vehicle:{id}", |Path(id): Path<u8>| -> u8 { id }),
+                ),
+        )
+        .command("Upper", || -> &'static str { "upper" })
+        .case_insensitive()
+        .finish()
+        .testing();
+    let (result, code) = unsafe { extension.call("hello:en", None) };
+    assert_eq!((result.as_str(), code), ("Hello", 0));
+    let (result, code) = unsafe { extension.call("HELLO:EN", None) };
+    assert_eq!((result.as_str(), code), ("Hello", 0));
+    let (result, code) = unsafe { extension.call("upper", None) };
+    assert_eq!((result.as_str(), code), ("upper", 0));
+    let (result, code) = unsafe { extension.call("hello:inner:VEHICLE:3", None) };
+    assert_eq!((result.as_str(), code), ("3", 0));
+
+    let commands = extension.ext.commands();
+    assert_eq!(commands[2].path(), "hello:english");
+    assert_eq!(commands[2].aliases(), ["hello:en"]);
+    assert_eq!(commands[2].deprecated(), None);
+
+    let extension = Extension::build()
+        .command("english", || -> &'static str { "Hello" })
+        .alias("en", "english")
+        .alias("fr", "french")
+        .deprecated("english", "use hello")
+        .finish()
+        .testing();
+    let (_, code) = unsafe { extension.call("EN", None) };
+    assert_eq!(code, 1);
+    assert_eq!(extension.ext.commands()[0].deprecated(), Some("use hello"));
+    let errors = Extension::build()
+        .command("english", || {})
+        .alias("english", "english")
+        .alias("fr", "french")
+        .try_finish()
+        .err()
+        .unwrap();
+    assert_eq!(
+        errors,
+        vec![
+            TreeError::DuplicateCommand("english".to_string()),
+            TreeError::UnknownAlias("fr".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn separator() {
+    let extension = Extension::build()
+        .separator('/')
+        .group(
+            "timer",
+            Group::new()
+                .command("sleep", |ms: u32| -> u32 { ms })
+                .command("mod:ace", || -> &'static str { "ace" })
+                .command("{id}/left", |Path(id): Path<String>| -> String { id }),
+        )
+        .finish()
+        .testing();
+    assert_eq!(extension.ext.separator(), '/');
+    let (result, code) = unsafe { extension.call("timer/sleep", Some(vec!["5".to_string()])) };
+    assert_eq!((result.as_str(), code), ("5", 0));
+    let (_, code) = unsafe { extension.call("timer:sleep", Some(vec!["5".to_string()])) };
+    assert_eq!(code, 1);
+    let (result, code) = unsafe { extension.call("timer/mod:ace", None) };
+    assert_eq!((result.as_str(), code), ("ace", 0));
+    let (result, code) = unsafe { extension.call("timer/a:b/left", None) };
+    assert_eq!((result.as_str(), code), ("a:b", 0));
+
+    assert!(extension
+        .ext
+        .schema()
+        .contains(r#""name":"sleep","path":"timer/sleep""#));
+    let files = SqfGenerator::new("my_ext", "MYX").generate(&extension.ext);
+    assert_eq!(files[0].0, "fn_timer_mod_ace.sqf");
+    assert_eq!(files[2].0, "fn_timer_p_id_left.sqf");
+    assert!(files[2]
+        .1
+        .contains(r#"("my_ext" callExtension [format ["timer/%1/left", _id], []])"#));
+
+    let errors = Extension::build()
+        .separator('/')
+        .group("a/b", Group::new())
+        .try_finish()
+        .err()
+        .unwrap();
+    assert_eq!(errors, vec![TreeError::Separator("a/b".to_string())]);
+}
+
+#[test]
+fn legacy_args() {
+    let extension = Extension::build()
+        .legacy_args('|')
+        .command("add", |a: i32, b: i32| -> i32 { a + b })
+        .command("hello", |name: String| -> String {
+            format!("Hello {}", name)
+        })
+        .command("ping", || -> &'static str { "pong" })
+        .finish()
+        .testing();
+    assert!(extension.ext.allow_no_args());
+    let (result, code) = unsafe { extension.call("add|1|2", None) };
+    assert_eq!((result.as_str(), code), ("3", 0));
+    let (result, code) = unsafe { extension.call("hello|\"John\"", None) };
+    assert_eq!((result.as_str(), code), ("Hello John", 0));
+    let (result, code) = unsafe { extension.call("ping", None) };
+    assert_eq!((result.as_str(), code), ("pong", 0));
+    let (_, code) = unsafe { extension.call("add|1", None) };
+    assert_eq!(code, 21);
+    let (_, code) = unsafe { extension.call("add|1|x", None) };
+    assert_eq!(code, 31);
+    let (result, code) =
+        unsafe { extension.call("add|1|2", Some(vec!["3".to_string(), "4".to_string()])) };
+    assert_eq!((result.as_str(), code), ("", 1));
+}
+
+#[test]
+fn router() {
+    let builder = Extension::build();
+    let router = builder.router();
+    router
+        .mount("early", Group::new().command("ping", || "pong"))
+        .unwrap();
+    let modules = {
+        let router = router.clone();
+        Group::new().command("enable", move || -> Result<&'static str, String> {
+            router
+                .mount(
+                    "ace",
+                    Group::new()
+                        .command("hello", || "Hello from ace")
+                        .command("disable", {
+                            let router = router.clone();
+                            move || -> &'static str {
+                                router.unmount("ace");
+                                "disabled"
+                            }
+                        }),
+                )
+                .map(|()| "enabled")
+                .map_err(|errors| errors[0].to_string())
+        })
+    };
+    let extension = builder
+        .group("modules", modules)
+        .command("vehicle:{id}:fuel", |Path(id): Path<u32>| id)
+        .finish()
+        .testing();
+    assert_eq!(router.mounted(), ["early"]);
+    let (result, code) = unsafe { extension.call("early:ping", None) };
+    assert_eq!((result.as_str(), code), ("pong", 0));
+
+    let (_, code) = unsafe { extension.call("ace:hello", None) };
+    assert_eq!(code, 1);
+    let (_, code) = unsafe { extension.call("modules:enable", None) };
+    assert_eq!(code, 0);
+    let (result, code) = unsafe { extension.call("ace:hello", None) };
+    assert_eq!((result.as_str(), code), ("Hello from ace", 0));
+    let paths = extension
+        .ext
+        .commands()
+        .iter()
+        .map(|command| command.path().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            "ace:disable",
+            "ace:hello",
+            "early:ping",
+            "modules:enable",
+            "vehicle:{id}:fuel"
+        ]
+    );
+    let (_, code) = unsafe { extension.call("modules:enable", None) };
+    assert_eq!(code, 9);
+
+    let (result, code) = unsafe { extension.call("ace:disable", None) };
+    assert_eq!((result.as_str(), code), ("disabled", 0));
+    let (_, code) = unsafe { extension.call("ace:hello", None) };
+    assert_eq!(code, 1);
+    assert_eq!(router.mounted(), ["early"]);
+
+    assert_eq!(
+        router.mount("modules", Group::new()),
+        Err(vec![TreeError::DuplicateGroup("modules".to_string())])
+    );
+    assert!(!router.unmount("modules"));
+    assert_eq!(
+        router.mount("vehicle", Group::new()),
+        Err(vec![TreeError::Shadowed("vehicle:{id}:fuel".to_string())])
+    );
+    let (result, code) = unsafe { extension.call("vehicle:7:fuel", None) };
+    assert_eq!((result.as_str(), code), ("7", 0));
+
+    let mounted = {
+        let router = router.clone();
+        std::thread::spawn(move || router.mount("late", Group::new().command("ping", || "pong")))
+    };
+    mounted.join().unwrap().unwrap();
+    let (result, code) = unsafe { extension.call("late:ping", None) };
+    assert_eq!((result.as_str(), code), ("pong", 0));
+}